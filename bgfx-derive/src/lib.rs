@@ -0,0 +1,497 @@
+// Copyright (c) 2015-2016, Johan Sköld.
+// License: http://opensource.org/licenses/ISC
+
+//! Derive macro for [`bgfx::Vertex`].
+//!
+//! Enable the `derive` feature of the `bgfx` crate to use it, rather than depending on this crate
+//! directly.
+//!
+//! Each field describing a vertex attribute is marked with `#[attrib(...)]`, naming the
+//! [`bgfx::Attrib`]. The component type and count are inferred from the field type:
+//!
+//! - `f32`, `u8`, `i8`, `u16` and `i16`, or arrays of 1 to 4 of them, map to the matching
+//!   [`bgfx::AttribType`] with that many components.
+//! - `u32` maps to 4 `Uint8` components, as used for packed colors.
+//!
+//! bgfx pads small attributes: 8- and 10-bit attributes always take 4 bytes, and 16-bit attributes
+//! take 4 bytes with 1 or 2 components, and 8 bytes with 3 or 4. Each field must be exactly as
+//! large as its attribute, which is checked at compile time. To declare fewer components than a
+//! field holds, set `count`, as in a `[u8; 4]` field with `count = 2`.
+//!
+//! The attribute takes these optional settings:
+//!
+//! - `normalized`: integer components are normalized to the 0 to 1 range.
+//! - `kind = Half`: overrides the component type, for types Rust has no equivalent of, such as
+//!   `Half` or `Uint10`.
+//! - `count = 3`: overrides the number of components.
+//!
+//! Fields without `#[attrib(...)]`, as well as any padding the compiler inserts, are skipped, so
+//! the stride of the declaration always matches the size of the struct. The struct must be
+//! `#[repr(C)]` or `#[repr(packed)]`, so its layout is predictable, and each attribute may only
+//! be declared once.
+//!
+//! The generated code uses `std::mem::offset_of!`, which requires Rust 1.77.
+//!
+//! # Example
+//!
+//! ```
+//! extern crate bgfx;
+//!
+//! use bgfx::Vertex;
+//!
+//! #[derive(Vertex)]
+//! #[repr(C)]
+//! struct PosColorVertex {
+//!     #[attrib(Position)]
+//!     pos: [f32; 3],
+//!
+//!     #[attrib(Color0, normalized)]
+//!     abgr: u32,
+//! }
+//!
+//! # fn main() {
+//! let decl = PosColorVertex::decl();
+//! assert_eq!(decl.stride(), 16);
+//! # }
+//! ```
+//!
+//! [`bgfx::Vertex`]: https://docs.rs/bgfx/*/bgfx/trait.Vertex.html
+//! [`bgfx::Attrib`]: https://docs.rs/bgfx/*/bgfx/enum.Attrib.html
+//! [`bgfx::AttribType`]: https://docs.rs/bgfx/*/bgfx/enum.AttribType.html
+
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
+extern crate syn;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use syn::spanned::Spanned;
+use syn::{Data, DeriveInput, Error, Expr, Fields, Ident, Lit, LitInt, Type};
+
+/// Names of the `bgfx::Attrib` variants.
+const ATTRIBS: &[&str] = &["Position", "Normal", "Tangent", "Bitangent", "Color0", "Color1",
+                           "Indices", "Weight", "TexCoord0", "TexCoord1", "TexCoord2",
+                           "TexCoord3", "TexCoord4", "TexCoord5", "TexCoord6", "TexCoord7"];
+
+/// Component type of an attribute, mirroring `bgfx::AttribType` without the normalized flag.
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Kind {
+    Uint8,
+    Int8,
+    Uint10,
+    Int10,
+    Uint16,
+    Int16,
+    Half,
+    Float,
+}
+
+impl Kind {
+
+    fn parse(ident: &Ident) -> Option<Kind> {
+        let kind = match &*ident.to_string() {
+            "Uint8" => Kind::Uint8,
+            "Int8" => Kind::Int8,
+            "Uint10" => Kind::Uint10,
+            "Int10" => Kind::Int10,
+            "Uint16" => Kind::Uint16,
+            "Int16" => Kind::Int16,
+            "Half" => Kind::Half,
+            "Float" => Kind::Float,
+            _ => return None,
+        };
+        Some(kind)
+    }
+
+    /// Maps a primitive Rust type to a component type.
+    fn from_primitive(name: &str) -> Option<Kind> {
+        match name {
+            "u8" => Some(Kind::Uint8),
+            "i8" => Some(Kind::Int8),
+            "u16" => Some(Kind::Uint16),
+            "i16" => Some(Kind::Int16),
+            "f32" => Some(Kind::Float),
+            _ => None,
+        }
+    }
+
+    fn is_integer(self) -> bool {
+        self != Kind::Half && self != Kind::Float
+    }
+
+    /// Size in bytes of an attribute with `count` components, as bgfx lays it out in
+    /// declarations built without a renderer.
+    fn size(self, count: u8) -> usize {
+        match self {
+            Kind::Uint8 | Kind::Int8 | Kind::Uint10 | Kind::Int10 => 4,
+            Kind::Uint16 | Kind::Int16 | Kind::Half => if count <= 2 { 4 } else { 8 },
+            Kind::Float => 4 * count as usize,
+        }
+    }
+
+    fn tokens(self, normalized: bool) -> TokenStream2 {
+        match self {
+            Kind::Uint8 => quote!(::bgfx::AttribType::Uint8(#normalized)),
+            Kind::Int8 => quote!(::bgfx::AttribType::Int8(#normalized)),
+            Kind::Uint10 => quote!(::bgfx::AttribType::Uint10(#normalized)),
+            Kind::Int10 => quote!(::bgfx::AttribType::Int10(#normalized)),
+            Kind::Uint16 => quote!(::bgfx::AttribType::Uint16(#normalized)),
+            Kind::Int16 => quote!(::bgfx::AttribType::Int16(#normalized)),
+            Kind::Half => quote!(::bgfx::AttribType::Half),
+            Kind::Float => quote!(::bgfx::AttribType::Float),
+        }
+    }
+
+}
+
+/// A field declared as a vertex attribute.
+struct AttribField {
+    member: TokenStream2,
+    ty: Type,
+    attrib: Ident,
+    kind: Kind,
+    count: u8,
+    normalized: bool,
+}
+
+/// Derives `bgfx::Vertex` for a struct. See the [crate documentation](index.html).
+#[proc_macro_derive(Vertex, attributes(attrib))]
+pub fn derive_vertex(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => compile_errors(err).into(),
+    }
+}
+
+/// Turns an error into `compile_error!` invocations. `Error::to_compile_error` refers to them
+/// through `::core`, which does not resolve in edition 2015 crates.
+fn compile_errors(err: Error) -> TokenStream2 {
+    err.into_iter()
+        .map(|err| {
+            let message = err.to_string();
+            quote_spanned!(err.span()=> compile_error!(#message);)
+        })
+        .collect()
+}
+
+fn expand(input: &DeriveInput) -> Result<TokenStream2, Error> {
+    let name = &input.ident;
+
+    if !input.generics.params.is_empty() {
+        return Err(Error::new(input.generics.span(),
+                              "#[derive(Vertex)] does not support generic structs"));
+    }
+
+    check_repr(input)?;
+
+    let fields = match input.data {
+        Data::Struct(ref data) => &data.fields,
+        _ => return Err(Error::new(Span::call_site(), "#[derive(Vertex)] only supports structs")),
+    };
+
+    let mut attribs = Vec::new();
+    for (index, field) in fields.iter().enumerate() {
+        let member = match field.ident {
+            Some(ref ident) => quote!(#ident),
+            None => {
+                let index = syn::Index::from(index);
+                quote!(#index)
+            }
+        };
+
+        for attr in &field.attrs {
+            if attr.path().is_ident("attrib") {
+                attribs.push(parse_attrib(attr, member.clone(), &field.ty)?);
+            }
+        }
+    }
+
+    if let Fields::Unit = *fields {
+        return Err(Error::new(Span::call_site(), "#[derive(Vertex)] needs at least one field"));
+    }
+
+    for (i, field) in attribs.iter().enumerate() {
+        if let Some(first) = attribs[..i].iter().find(|first| first.attrib == field.attrib) {
+            return Err(Error::new(field.attrib.span(),
+                                  format!("attribute `{}` is already declared by field `{}`",
+                                          field.attrib,
+                                          first.member)));
+        }
+    }
+
+    // Since each field is as large as its attribute, and the gaps between them are skipped, the
+    // stride of the declaration always matches the size of the struct.
+    let size_checks = attribs.iter().map(|field| {
+        let ty = &field.ty;
+        let size = field.kind.size(field.count);
+        let message = format!("field `{}` of `{}` must be {} bytes, the size of its attribute",
+                              field.member,
+                              name,
+                              size);
+        quote_spanned! {field.ty.span()=>
+            assert!(::std::mem::size_of::<#ty>() == #size, #message);
+        }
+    });
+
+    let stride_message = format!("`{}` is larger than the maximum stride of 65535 bytes", name);
+
+    let adds = attribs.iter().map(|field| {
+        let member = &field.member;
+        let attrib = &field.attrib;
+        let count = field.count;
+        let kind = field.kind.tokens(field.normalized);
+        quote! {
+            pad(&mut builder, ::std::mem::offset_of!(#name, #member));
+            builder.add(::bgfx::Attrib::#attrib, #count, #kind);
+        }
+    });
+
+    Ok(quote! {
+        const _: () = {
+            #(#size_checks)*
+            assert!(::std::mem::size_of::<#name>() <= 0xffff, #stride_message);
+
+            impl ::bgfx::Vertex for #name {
+                fn decl() -> ::bgfx::VertexDecl {
+                    /// Skips up to offset `to`, at most 255 bytes at a time.
+                    fn pad(builder: &mut ::bgfx::VertexDeclBuilder, to: usize) {
+                        while (builder.stride() as usize) < to {
+                            let bytes = ::std::cmp::min(to - builder.stride() as usize, 255);
+                            builder.skip(bytes as u8);
+                        }
+                    }
+
+                    let mut builder = ::bgfx::VertexDecl::new(None);
+                    #(#adds)*
+                    pad(&mut builder, ::std::mem::size_of::<#name>());
+                    builder.end()
+                }
+            }
+        };
+    })
+}
+
+/// Requires `#[repr(C)]` or `#[repr(packed)]`, since the layout of other structs is unspecified.
+fn check_repr(input: &DeriveInput) -> Result<(), Error> {
+    let mut stable = false;
+
+    for attr in &input.attrs {
+        if attr.path().is_ident("repr") {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("C") || meta.path.is_ident("packed") {
+                    stable = true;
+                }
+                // Skip the arguments of other hints, such as `align(16)`.
+                if meta.input.peek(syn::token::Paren) {
+                    let content;
+                    syn::parenthesized!(content in meta.input);
+                    content.parse::<TokenStream2>()?;
+                }
+                Ok(())
+            })?;
+        }
+    }
+
+    if stable {
+        Ok(())
+    } else {
+        Err(Error::new(input.ident.span(),
+                       "#[derive(Vertex)] requires #[repr(C)] or #[repr(packed)]"))
+    }
+}
+
+fn parse_attrib(attr: &syn::Attribute,
+                member: TokenStream2,
+                ty: &Type)
+                -> Result<AttribField, Error> {
+    let mut attrib = None;
+    let mut kind = None;
+    let mut count = None;
+    let mut normalized = false;
+
+    attr.parse_nested_meta(|meta| {
+        if meta.path.is_ident("normalized") {
+            normalized = true;
+        } else if meta.path.is_ident("kind") {
+            let ident: Ident = meta.value()?.parse()?;
+            kind = Some(Kind::parse(&ident).ok_or_else(|| {
+                Error::new(ident.span(), format!("unknown attribute type `{}`", ident))
+            })?);
+        } else if meta.path.is_ident("count") {
+            let lit: LitInt = meta.value()?.parse()?;
+            count = Some(parse_count(&lit)?);
+        } else if let Some(ident) = meta.path.get_ident() {
+            if attrib.is_some() {
+                return Err(meta.error("attribute given more than once"));
+            }
+            if !ATTRIBS.contains(&&*ident.to_string()) {
+                return Err(meta.error(format!("unknown vertex attribute `{}`", ident)));
+            }
+            attrib = Some(ident.clone());
+        } else {
+            return Err(meta.error("unsupported setting"));
+        }
+        Ok(())
+    })?;
+
+    let attrib = attrib.ok_or_else(|| {
+        Error::new(attr.span(), "missing vertex attribute, as in #[attrib(Position)]")
+    })?;
+
+    let (inferred_kind, inferred_count) = infer(ty);
+    let kind = kind.or(inferred_kind).ok_or_else(|| {
+        Error::new(ty.span(), "cannot infer the attribute type, specify it with `kind = ...`")
+    })?;
+    if count.is_none() && (kind == Kind::Uint10 || kind == Kind::Int10) {
+        return Err(Error::new(attr.span(), "10-bit attributes need `count = ...`"));
+    }
+    let count = count.or(inferred_count).ok_or_else(|| {
+        Error::new(ty.span(), "cannot infer the component count, specify it with `count = ...`")
+    })?;
+
+    if normalized && !kind.is_integer() {
+        return Err(Error::new(attr.span(), "only integer attributes can be normalized"));
+    }
+
+    Ok(AttribField {
+        member: member,
+        ty: ty.clone(),
+        attrib: attrib,
+        kind: kind,
+        count: count,
+        normalized: normalized,
+    })
+}
+
+fn parse_count(lit: &LitInt) -> Result<u8, Error> {
+    match lit.base10_parse::<u8>() {
+        Ok(count) if (1..=4).contains(&count) => Ok(count),
+        _ => Err(Error::new(lit.span(), "attributes have 1 to 4 components")),
+    }
+}
+
+/// Infers the component type and count from a field type, where possible.
+fn infer(ty: &Type) -> (Option<Kind>, Option<u8>) {
+    match *ty {
+        Type::Path(ref path) => {
+            match path.path.get_ident().map(|ident| ident.to_string()) {
+                Some(ref name) if name == "u32" => (Some(Kind::Uint8), Some(4)),
+                Some(ref name) => (Kind::from_primitive(name), Some(1)),
+                None => (None, None),
+            }
+        }
+        Type::Array(ref array) => {
+            let kind = match *array.elem {
+                Type::Path(ref path) => {
+                    path.path.get_ident().and_then(|ident| Kind::from_primitive(&ident.to_string()))
+                }
+                _ => None,
+            };
+            let count = match array.len {
+                Expr::Lit(ref expr) => {
+                    match expr.lit {
+                        Lit::Int(ref lit) => parse_count(lit).ok(),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            };
+            (kind, count)
+        }
+        _ => (None, None),
+    }
+}
+
+/// Structs `#[derive(Vertex)]` must reject.
+///
+/// Without a stable layout:
+///
+/// ```compile_fail
+/// extern crate bgfx;
+///
+/// use bgfx::Vertex;
+///
+/// #[derive(Vertex)]
+/// struct Unordered {
+///     #[attrib(Position)]
+///     pos: [f32; 3],
+/// }
+/// # fn main() {}
+/// ```
+///
+/// With a field smaller than its attribute, since bgfx pads 8-bit attributes to 4 bytes:
+///
+/// ```compile_fail
+/// extern crate bgfx;
+///
+/// use bgfx::Vertex;
+///
+/// #[derive(Vertex)]
+/// #[repr(C)]
+/// struct Small {
+///     #[attrib(Position)]
+///     pos: [f32; 3],
+///
+///     #[attrib(Color0, normalized)]
+///     rgb: [u8; 3],
+/// }
+/// # fn main() {}
+/// ```
+///
+/// With a field larger than its attribute:
+///
+/// ```compile_fail
+/// extern crate bgfx;
+///
+/// use bgfx::Vertex;
+///
+/// #[derive(Vertex)]
+/// #[repr(C)]
+/// struct Large {
+///     #[attrib(Position, count = 2)]
+///     pos: [f32; 3],
+/// }
+/// # fn main() {}
+/// ```
+///
+/// With an attribute declared twice:
+///
+/// ```compile_fail
+/// extern crate bgfx;
+///
+/// use bgfx::Vertex;
+///
+/// #[derive(Vertex)]
+/// #[repr(C)]
+/// struct Twice {
+///     #[attrib(Position)]
+///     pos: [f32; 3],
+///
+///     #[attrib(Position)]
+///     other: [f32; 3],
+/// }
+/// # fn main() {}
+/// ```
+///
+/// With an unknown attribute:
+///
+/// ```compile_fail
+/// extern crate bgfx;
+///
+/// use bgfx::Vertex;
+///
+/// #[derive(Vertex)]
+/// #[repr(C)]
+/// struct Unknown {
+///     #[attrib(Texcoord0)]
+///     uv: [f32; 2],
+/// }
+/// # fn main() {}
+/// ```
+#[cfg(doctest)]
+#[allow(dead_code)]
+struct Rejected;
@@ -0,0 +1,148 @@
+// Copyright (c) 2015-2016, Johan Sköld.
+// License: http://opensource.org/licenses/ISC
+
+extern crate bgfx;
+
+use bgfx::{Attrib, AttribInfo, AttribType, Vertex, VertexDecl};
+
+fn info(decl: &VertexDecl, attrib: Attrib) -> (u16, u8, AttribType) {
+    let AttribInfo { offset, count, kind, .. } = decl.attrib(attrib).unwrap();
+    (offset, count, kind)
+}
+
+#[derive(Vertex)]
+#[repr(C)]
+#[allow(dead_code)]
+struct PosColorVertex {
+    #[attrib(Position)]
+    pos: [f32; 3],
+
+    #[attrib(Color0, normalized)]
+    abgr: u32,
+}
+
+#[test]
+fn u32_is_a_packed_color() {
+    let decl = PosColorVertex::decl();
+
+    assert_eq!(decl.stride(), 16);
+    assert_eq!(info(&decl, Attrib::Position), (0, 3, AttribType::Float));
+    assert_eq!(info(&decl, Attrib::Color0), (12, 4, AttribType::Uint8(true)));
+    assert_eq!(decl.attribs().count(), 2);
+}
+
+#[derive(Vertex)]
+#[repr(C)]
+#[allow(dead_code)]
+struct Gaps {
+    #[attrib(Position)]
+    pos: [f32; 3],
+
+    flags: u8,
+
+    #[attrib(TexCoord0)]
+    uv: [f32; 2],
+
+    scratch: [u8; 600],
+
+    #[attrib(Normal)]
+    normal: [f32; 3],
+
+    last: u8,
+}
+
+#[test]
+fn unmarked_fields_and_padding_are_skipped() {
+    let decl = Gaps::decl();
+
+    assert_eq!(decl.stride() as usize, std::mem::size_of::<Gaps>());
+    assert_eq!(info(&decl, Attrib::Position), (0, 3, AttribType::Float));
+    assert_eq!(info(&decl, Attrib::TexCoord0), (16, 2, AttribType::Float));
+    assert_eq!(info(&decl, Attrib::Normal), (624, 3, AttribType::Float));
+    assert_eq!(decl.attribs().count(), 3);
+}
+
+#[derive(Vertex)]
+#[repr(C, align(16))]
+#[allow(dead_code)]
+struct Aligned {
+    #[attrib(Position)]
+    pos: [f32; 2],
+}
+
+#[test]
+fn trailing_padding_is_skipped() {
+    let decl = Aligned::decl();
+
+    assert_eq!(decl.stride(), 16);
+    assert_eq!(info(&decl, Attrib::Position), (0, 2, AttribType::Float));
+}
+
+#[derive(Vertex)]
+#[repr(C)]
+#[allow(dead_code)]
+struct Overrides {
+    #[attrib(Normal, kind = Uint10, count = 3, normalized)]
+    normal: u32,
+
+    #[attrib(TexCoord0, kind = Half)]
+    uv: [u16; 2],
+
+    #[attrib(TexCoord1, kind = Half, count = 3)]
+    uvw: [u16; 4],
+
+    #[attrib(Color1, count = 2, normalized)]
+    rg: [u8; 4],
+
+    #[attrib(Indices, count = 4)]
+    indices: [i16; 4],
+}
+
+#[test]
+fn kind_and_count_override_the_field_type() {
+    let decl = Overrides::decl();
+
+    assert_eq!(decl.stride(), 28);
+    assert_eq!(info(&decl, Attrib::Normal), (0, 3, AttribType::Uint10(true)));
+    assert_eq!(info(&decl, Attrib::TexCoord0), (4, 2, AttribType::Half));
+    assert_eq!(info(&decl, Attrib::TexCoord1), (8, 3, AttribType::Half));
+    assert_eq!(info(&decl, Attrib::Color1), (16, 2, AttribType::Uint8(true)));
+    assert_eq!(info(&decl, Attrib::Indices), (20, 4, AttribType::Int16(false)));
+}
+
+#[derive(Vertex)]
+#[repr(C, packed)]
+#[allow(dead_code)]
+struct Packed {
+    #[attrib(Position)]
+    pos: [f32; 3],
+
+    flags: u8,
+
+    #[attrib(Color0, normalized)]
+    abgr: u32,
+}
+
+#[test]
+fn packed_structs_keep_their_offsets() {
+    let decl = Packed::decl();
+
+    assert_eq!(decl.stride(), 17);
+    assert_eq!(info(&decl, Attrib::Position), (0, 3, AttribType::Float));
+    assert_eq!(info(&decl, Attrib::Color0), (13, 4, AttribType::Uint8(true)));
+}
+
+#[derive(Vertex)]
+#[repr(C)]
+#[allow(dead_code)]
+struct Tuple(#[attrib(Position)] [f32; 2], u32, #[attrib(TexCoord0)] [f32; 2]);
+
+#[test]
+fn tuple_structs_are_supported() {
+    let decl = Tuple::decl();
+
+    assert_eq!(decl.stride(), 20);
+    assert_eq!(info(&decl, Attrib::Position), (0, 2, AttribType::Float));
+    assert_eq!(info(&decl, Attrib::TexCoord0), (12, 2, AttribType::Float));
+    assert_eq!(decl.attrib(Attrib::Color0), None);
+}
@@ -18,7 +18,7 @@ fn example(events: EventQueue) {
     let debug = DEBUG_TEXT;
     let reset = RESET_VSYNC;
 
-    let bgfx = bgfx::init(RendererType::Default, None, None).unwrap();
+    let bgfx = bgfx::init(RendererType::Default, None, None, None, None).unwrap();
     bgfx.reset(width, height, reset);
 
     // Enable debug text.
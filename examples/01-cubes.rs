@@ -211,7 +211,7 @@ impl<'a> Cubes<'a> {
 }
 
 fn example(events: EventQueue) {
-    let bgfx = bgfx::init(RendererType::Default, None, None).unwrap();
+    let bgfx = bgfx::init(RendererType::Default, None, None, None, None).unwrap();
     let mut cubes = Cubes::new(&bgfx, events);
     cubes.init();
     while cubes.update() {}
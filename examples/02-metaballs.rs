@@ -778,10 +778,10 @@ impl<'a> Metaballs<'a> {
 }
 
 fn example(events: EventQueue) {
-    let bgfx = bgfx::init(RendererType::OpenGL, None, None).unwrap();
-    // let bgfx = bgfx::init(RendererType::Vulkan, None, None).unwrap();
-    // let bgfx = bgfx::init(RendererType::Direct3D9, None, None).unwrap();
-    // let bgfx = bgfx::init(RendererType::Direct3D11, None, None).unwrap();
+    let bgfx = bgfx::init(RendererType::OpenGL, None, None, None, None).unwrap();
+    // let bgfx = bgfx::init(RendererType::Vulkan, None, None, None, None).unwrap();
+    // let bgfx = bgfx::init(RendererType::Direct3D9, None, None, None, None).unwrap();
+    // let bgfx = bgfx::init(RendererType::Direct3D11, None, None, None, None).unwrap();
     let mut metaballs = Metaballs::new(&bgfx, events);
     metaballs.init();
     while metaballs.update() {}
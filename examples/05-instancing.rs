@@ -15,21 +15,14 @@ use common::*;
 use time::PreciseTime;
 
 
-#[repr(packed)]
+#[derive(Vertex)]
+#[repr(C)]
 struct PosColorVertex {
-    _x: f32,
-    _y: f32,
-    _z: f32,
-    _abgr: u32,
-}
+    #[attrib(Position)]
+    _pos: [f32; 3],
 
-impl PosColorVertex {
-    fn build_decl() -> VertexDecl {
-        VertexDecl::new(None)
-            .add(Attrib::Position, 3, AttribType::Float)
-            .add(Attrib::Color0, 4, AttribType::Uint8(true))
-            .end()
-    }
+    #[attrib(Color0, normalized)]
+    _abgr: u32,
 }
 
 #[repr(C)]
@@ -40,14 +33,14 @@ struct InstanceData {
 
 #[cfg_attr(rustfmt, rustfmt_skip)]
 static CUBE_VERTICES: [PosColorVertex; 8] = [
-    PosColorVertex { _x: -1.0, _y:  1.0, _z:  1.0, _abgr: 0xff000000 },
-    PosColorVertex { _x:  1.0, _y:  1.0, _z:  1.0, _abgr: 0xff0000ff },
-    PosColorVertex { _x: -1.0, _y: -1.0, _z:  1.0, _abgr: 0xff00ff00 },
-    PosColorVertex { _x:  1.0, _y: -1.0, _z:  1.0, _abgr: 0xff00ffff },
-    PosColorVertex { _x: -1.0, _y:  1.0, _z: -1.0, _abgr: 0xffff0000 },
-    PosColorVertex { _x:  1.0, _y:  1.0, _z: -1.0, _abgr: 0xffff00ff },
-    PosColorVertex { _x: -1.0, _y: -1.0, _z: -1.0, _abgr: 0xffffff00 },
-    PosColorVertex { _x:  1.0, _y: -1.0, _z: -1.0, _abgr: 0xffffffff },
+    PosColorVertex { _pos: [-1.0,  1.0,  1.0], _abgr: 0xff000000 },
+    PosColorVertex { _pos: [ 1.0,  1.0,  1.0], _abgr: 0xff0000ff },
+    PosColorVertex { _pos: [-1.0, -1.0,  1.0], _abgr: 0xff00ff00 },
+    PosColorVertex { _pos: [ 1.0, -1.0,  1.0], _abgr: 0xff00ffff },
+    PosColorVertex { _pos: [-1.0,  1.0, -1.0], _abgr: 0xffff0000 },
+    PosColorVertex { _pos: [ 1.0,  1.0, -1.0], _abgr: 0xffff00ff },
+    PosColorVertex { _pos: [-1.0, -1.0, -1.0], _abgr: 0xffffff00 },
+    PosColorVertex { _pos: [ 1.0, -1.0, -1.0], _abgr: 0xffffffff },
 ];
 
 #[cfg_attr(rustfmt, rustfmt_skip)]
@@ -73,8 +66,8 @@ struct Cubes<'a> {
     height: u16,
     debug: DebugFlags,
     reset: ResetFlags,
-    vbh: Option<VertexBuffer<'a>>,
-    ibh: Option<IndexBuffer<'a>>,
+    vbh: Option<VertexBuffer<'a, PosColorVertex>>,
+    ibh: Option<IndexBuffer<'a, u16>>,
     program: Option<Program<'a>>,
     time: Option<PreciseTime>,
     last: Option<PreciseTime>,
@@ -117,16 +110,13 @@ impl<'a> Cubes<'a> {
         let clear_flags = CLEAR_COLOR | CLEAR_DEPTH;
         self.bgfx.set_view_clear(0, clear_flags, 0x303030ff, 1.0_f32, 0);
 
-        // Create vertex stream declaration
-        let decl = PosColorVertex::build_decl();
-
-        // Create static vertex buffer.
-        self.vbh = Some(VertexBuffer::new(Memory::reference(self.bgfx, &CUBE_VERTICES),
-                                          &decl,
-                                          BUFFER_NONE));
+        // Create static vertex buffer, declared by `#[derive(Vertex)]`.
+        self.vbh = Some(VertexBuffer::from_slice(self.bgfx, &CUBE_VERTICES, BUFFER_NONE)
+            .expect("Failed to create vertex buffer"));
 
         // Create static index buffer.
-        self.ibh = Some(IndexBuffer::new(Memory::reference(self.bgfx, &CUBE_INDICES), BUFFER_NONE));
+        self.ibh = Some(IndexBuffer::from_slice(self.bgfx, &CUBE_INDICES, BUFFER_NONE)
+            .expect("Failed to create index buffer"));
 
         // Create program from shaders.
         self.program = Some(common::load_program(&self.bgfx, "vs_instancing", "fs_instancing"));
@@ -242,7 +232,7 @@ impl<'a> Cubes<'a> {
 }
 
 fn example(events: EventQueue) {
-    let bgfx = bgfx::init(RendererType::Default, None, None).unwrap();
+    let bgfx = bgfx::init(RendererType::Default, None, None, None, None).unwrap();
     let mut cubes = Cubes::new(&bgfx, events);
     cubes.init();
     while cubes.update() {}
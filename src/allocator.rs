@@ -0,0 +1,450 @@
+// Copyright (c) 2015-2016, Johan Sköld.
+// License: http://opensource.org/licenses/ISC
+
+//! Custom memory allocation for bgfx.
+//!
+//! Implement [`Allocator`] and pass it to [`bgfx::init`] to route all of bgfx' allocations
+//! through it. [`RustAllocator`] forwards to Rust's global allocator, and [`CountingAllocator`]
+//! additionally keeps track of how much memory bgfx is using.
+//!
+//! [`Allocator`]: trait.Allocator.html
+//! [`RustAllocator`]: struct.RustAllocator.html
+//! [`CountingAllocator`]: struct.CountingAllocator.html
+//! [`bgfx::init`]: ../fn.init.html
+
+use std::alloc::{self, Layout};
+use std::cmp;
+use std::ffi::CStr;
+use std::mem;
+use std::os::raw::{c_char, c_void};
+use std::panic::{self, AssertUnwindSafe};
+use std::process;
+use std::ptr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use bgfx_sys;
+
+/// Alignment bgfx assumes when it asks for an alignment of 8 or less.
+const NATURAL_ALIGNMENT: usize = 8;
+
+/// Allocates memory on behalf of bgfx.
+///
+/// bgfx calls this from both the main thread and the render thread, hence the `Send + Sync`
+/// requirement. A panic inside `realloc` aborts the process, as it is not allowed to unwind into
+/// bgfx.
+pub trait Allocator: Send + Sync {
+
+    /// Allocates, reallocates or frees memory, following the semantics of C's `realloc`:
+    ///
+    /// - If `size` is 0, `ptr` is freed (if non-null) and null is returned.
+    /// - If `ptr` is null, a new block of `size` bytes is returned.
+    /// - Otherwise `ptr` is resized to `size` bytes, preserving its contents.
+    ///
+    /// Returned blocks must be aligned to `align` bytes, or to 8 bytes if `align` is smaller than
+    /// that. `file` and `line` identify the allocation site inside bgfx, and are empty and 0 in
+    /// builds without that information.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be null, or a block previously returned by this allocator that has not been
+    /// freed yet.
+    unsafe fn realloc(&self,
+                      ptr: *mut c_void,
+                      size: usize,
+                      align: usize,
+                      file: &str,
+                      line: u32)
+                      -> *mut c_void;
+
+}
+
+impl<A: Allocator + ?Sized> Allocator for Arc<A> {
+
+    #[inline]
+    unsafe fn realloc(&self,
+                      ptr: *mut c_void,
+                      size: usize,
+                      align: usize,
+                      file: &str,
+                      line: u32)
+                      -> *mut c_void {
+        (**self).realloc(ptr, size, align, file, line)
+    }
+
+}
+
+/// Allocator forwarding to Rust's global allocator.
+///
+/// Each block is prefixed with a small header recording its size and alignment, since the global
+/// allocator needs both to free a block.
+#[derive(Debug, Default)]
+pub struct RustAllocator;
+
+/// Header stored directly in front of each block handed out by `RustAllocator`.
+struct Header {
+    size: usize,
+    align: usize,
+}
+
+impl RustAllocator {
+
+    /// Creates a new allocator.
+    #[inline]
+    pub fn new() -> RustAllocator {
+        RustAllocator
+    }
+
+    /// Returns the layout of a block of `size` bytes aligned to `align`, including its header. The
+    /// header occupies the first `align` bytes, so the returned pointer stays aligned.
+    fn layout(size: usize, align: usize) -> Option<Layout> {
+        let align = cmp::max(cmp::max(align, NATURAL_ALIGNMENT), mem::size_of::<Header>())
+            .next_power_of_two();
+        size.checked_add(align).and_then(|total| Layout::from_size_align(total, align).ok())
+    }
+
+    /// Returns the header of a block returned by this allocator.
+    unsafe fn header(ptr: *mut c_void) -> *mut Header {
+        (ptr as *mut u8).sub(mem::size_of::<Header>()) as *mut Header
+    }
+
+    /// Returns the size of a block returned by this allocator.
+    unsafe fn size_of(ptr: *mut c_void) -> usize {
+        (*RustAllocator::header(ptr)).size
+    }
+
+    unsafe fn alloc(size: usize, align: usize) -> *mut c_void {
+        let layout = match RustAllocator::layout(size, align) {
+            Some(layout) => layout,
+            None => return ptr::null_mut(),
+        };
+
+        let base = alloc::alloc(layout);
+        if base.is_null() {
+            return ptr::null_mut();
+        }
+
+        let ptr = base.add(layout.align()) as *mut c_void;
+        ptr::write(RustAllocator::header(ptr), Header { size: size, align: layout.align() });
+        ptr
+    }
+
+    unsafe fn free(ptr: *mut c_void) {
+        let Header { size, align } = ptr::read(RustAllocator::header(ptr));
+        let base = (ptr as *mut u8).sub(align);
+        alloc::dealloc(base, Layout::from_size_align_unchecked(size + align, align));
+    }
+
+}
+
+impl Allocator for RustAllocator {
+
+    unsafe fn realloc(&self,
+                      ptr: *mut c_void,
+                      size: usize,
+                      align: usize,
+                      _file: &str,
+                      _line: u32)
+                      -> *mut c_void {
+        if size == 0 {
+            if !ptr.is_null() {
+                RustAllocator::free(ptr);
+            }
+            return ptr::null_mut();
+        }
+
+        if ptr.is_null() {
+            return RustAllocator::alloc(size, align);
+        }
+
+        let Header { size: old_size, align: old_align } = ptr::read(RustAllocator::header(ptr));
+        let layout = match RustAllocator::layout(size, align) {
+            Some(layout) => layout,
+            None => return ptr::null_mut(),
+        };
+
+        if layout.align() == old_align {
+            // Same alignment, so the global allocator can resize the block in place.
+            let base = (ptr as *mut u8).sub(old_align);
+            let old_layout = Layout::from_size_align_unchecked(old_size + old_align, old_align);
+            let base = alloc::realloc(base, old_layout, layout.size());
+            if base.is_null() {
+                return ptr::null_mut();
+            }
+
+            let ptr = base.add(old_align) as *mut c_void;
+            ptr::write(RustAllocator::header(ptr), Header { size: size, align: old_align });
+            ptr
+        } else {
+            let new_ptr = RustAllocator::alloc(size, align);
+            if !new_ptr.is_null() {
+                ptr::copy_nonoverlapping(ptr as *const u8,
+                                         new_ptr as *mut u8,
+                                         cmp::min(size, old_size));
+                RustAllocator::free(ptr);
+            }
+            new_ptr
+        }
+    }
+
+}
+
+/// Allocator keeping track of bgfx' memory usage.
+///
+/// Forwards to [`RustAllocator`], while counting live bytes, live allocations and peak usage.
+/// Wrap it in an `Arc` to be able to query it after handing it to [`bgfx::init`].
+///
+/// # Example
+///
+/// ```no_run
+/// use std::sync::Arc;
+///
+/// let allocator = Arc::new(bgfx::CountingAllocator::new());
+/// let bgfx = bgfx::init(bgfx::RendererType::Default,
+///                       None,
+///                       None,
+///                       None,
+///                       Some(Box::new(allocator.clone())))
+///     .expect("Failed to initialize bgfx");
+///
+/// // ...
+///
+/// println!("bgfx is using {} bytes", allocator.live_bytes());
+/// ```
+///
+/// [`RustAllocator`]: struct.RustAllocator.html
+/// [`bgfx::init`]: ../fn.init.html
+#[derive(Debug, Default)]
+pub struct CountingAllocator {
+    live_bytes: AtomicUsize,
+    live_allocations: AtomicUsize,
+    peak_bytes: AtomicUsize,
+}
+
+impl CountingAllocator {
+
+    /// Creates a new allocator, with all counters at zero.
+    #[inline]
+    pub fn new() -> CountingAllocator {
+        Default::default()
+    }
+
+    /// Number of bytes currently allocated by bgfx.
+    #[inline]
+    pub fn live_bytes(&self) -> usize {
+        self.live_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Number of blocks currently allocated by bgfx.
+    #[inline]
+    pub fn live_allocations(&self) -> usize {
+        self.live_allocations.load(Ordering::Relaxed)
+    }
+
+    /// Highest number of bytes allocated by bgfx at any one time.
+    #[inline]
+    pub fn peak_bytes(&self) -> usize {
+        self.peak_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Resets the peak to the number of bytes currently allocated.
+    #[inline]
+    pub fn reset_peak(&self) {
+        self.peak_bytes.store(self.live_bytes(), Ordering::Relaxed);
+    }
+
+    fn add(&self, bytes: usize) {
+        let live = self.live_bytes.fetch_add(bytes, Ordering::Relaxed) + bytes;
+        self.peak_bytes.fetch_max(live, Ordering::Relaxed);
+    }
+
+    fn sub(&self, bytes: usize) {
+        self.live_bytes.fetch_sub(bytes, Ordering::Relaxed);
+    }
+
+}
+
+impl Allocator for CountingAllocator {
+
+    unsafe fn realloc(&self,
+                      ptr: *mut c_void,
+                      size: usize,
+                      align: usize,
+                      file: &str,
+                      line: u32)
+                      -> *mut c_void {
+        let old_size = if ptr.is_null() { 0 } else { RustAllocator::size_of(ptr) };
+        let new_ptr = RustAllocator.realloc(ptr, size, align, file, line);
+
+        if size == 0 {
+            if !ptr.is_null() {
+                self.sub(old_size);
+                self.live_allocations.fetch_sub(1, Ordering::Relaxed);
+            }
+        } else if !new_ptr.is_null() {
+            if ptr.is_null() {
+                self.live_allocations.fetch_add(1, Ordering::Relaxed);
+            }
+            self.sub(old_size);
+            self.add(size);
+        }
+
+        new_ptr
+    }
+
+}
+
+/// The allocator interface handed to bgfx.
+///
+/// bgfx only knows about the leading `interface` field, and passes a pointer to it back into
+/// `realloc`. Since the struct is `repr(C)`, that pointer can be cast back to the shim.
+#[repr(C)]
+pub(crate) struct AllocatorShim {
+    interface: bgfx_sys::bgfx_allocator_interface_t,
+    allocator: Box<dyn Allocator>,
+}
+
+// The interface only points to a static vtable, and the other fields are `Send + Sync` anyway.
+unsafe impl Send for AllocatorShim {}
+unsafe impl Sync for AllocatorShim {}
+
+impl AllocatorShim {
+
+    pub(crate) fn new(allocator: Box<dyn Allocator>) -> Box<AllocatorShim> {
+        Box::new(AllocatorShim {
+            interface: bgfx_sys::bgfx_allocator_interface_t { vtbl: &ALLOCATOR_VTBL },
+            allocator: allocator,
+        })
+    }
+
+    /// Returns the pointer to pass to `bgfx_init`. It remains valid for as long as the box lives.
+    pub(crate) fn as_ptr(&mut self) -> *mut bgfx_sys::bgfx_allocator_interface_t {
+        &mut self.interface
+    }
+
+}
+
+static ALLOCATOR_VTBL: bgfx_sys::bgfx_allocator_vtbl_t = bgfx_sys::bgfx_allocator_vtbl_t {
+    realloc: Some(shim_realloc),
+};
+
+unsafe extern "C" fn shim_realloc(this: *mut bgfx_sys::bgfx_allocator_interface_t,
+                                  ptr: *mut c_void,
+                                  size: usize,
+                                  align: usize,
+                                  file: *const c_char,
+                                  line: u32)
+                                  -> *mut c_void {
+    let shim = &*(this as *const AllocatorShim);
+    let file = if file.is_null() { "".into() } else { CStr::from_ptr(file).to_string_lossy() };
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        shim.allocator.realloc(ptr, size, align, &file, line)
+    }));
+
+    match result {
+        Ok(ptr) => ptr,
+        Err(_) => process::abort(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::os::raw::c_void;
+    use std::ptr;
+
+    use super::*;
+
+    unsafe fn realloc(allocator: &dyn Allocator,
+                      ptr: *mut c_void,
+                      size: usize,
+                      align: usize)
+                      -> *mut c_void {
+        allocator.realloc(ptr, size, align, "", 0)
+    }
+
+    #[test]
+    fn layout_reserves_aligned_header() {
+        for &align in &[0, 1, 4, 8, 16, 64, 4096] {
+            let layout = RustAllocator::layout(100, align).unwrap();
+            assert!(layout.align() >= cmp::max(align, NATURAL_ALIGNMENT));
+            assert!(layout.align() >= mem::size_of::<Header>());
+            assert!(layout.align().is_power_of_two());
+            assert_eq!(layout.size(), 100 + layout.align());
+        }
+
+        assert!(RustAllocator::layout(usize::MAX, 16).is_none());
+    }
+
+    #[test]
+    fn alloc_is_aligned_and_records_size() {
+        unsafe {
+            for &align in &[0, 8, 16, 32, 256] {
+                let ptr = realloc(&RustAllocator, ptr::null_mut(), 24, align);
+                assert!(!ptr.is_null());
+                assert_eq!(ptr as usize % cmp::max(align, NATURAL_ALIGNMENT), 0);
+                assert_eq!(RustAllocator::size_of(ptr), 24);
+                assert!(realloc(&RustAllocator, ptr, 0, align).is_null());
+            }
+        }
+    }
+
+    #[test]
+    fn realloc_preserves_contents() {
+        unsafe {
+            let ptr = realloc(&RustAllocator, ptr::null_mut(), 16, 8) as *mut u8;
+            for i in 0..16 {
+                *ptr.add(i) = i as u8;
+            }
+
+            // Same alignment, resized in place by the global allocator.
+            let ptr = realloc(&RustAllocator, ptr as *mut c_void, 4096, 8) as *mut u8;
+            assert_eq!(RustAllocator::size_of(ptr as *mut c_void), 4096);
+            for i in 0..16 {
+                assert_eq!(*ptr.add(i), i as u8);
+            }
+
+            // Different alignment, moved to a new block.
+            let ptr = realloc(&RustAllocator, ptr as *mut c_void, 8, 128) as *mut u8;
+            assert_eq!(ptr as usize % 128, 0);
+            assert_eq!(RustAllocator::size_of(ptr as *mut c_void), 8);
+            for i in 0..8 {
+                assert_eq!(*ptr.add(i), i as u8);
+            }
+
+            realloc(&RustAllocator, ptr as *mut c_void, 0, 128);
+        }
+    }
+
+    #[test]
+    fn counting_allocator_tracks_usage() {
+        let allocator = CountingAllocator::new();
+
+        unsafe {
+            let a = realloc(&allocator, ptr::null_mut(), 100, 0);
+            let b = realloc(&allocator, ptr::null_mut(), 50, 16);
+            assert_eq!(allocator.live_bytes(), 150);
+            assert_eq!(allocator.live_allocations(), 2);
+
+            let a = realloc(&allocator, a, 20, 0);
+            assert_eq!(allocator.live_bytes(), 70);
+            assert_eq!(allocator.live_allocations(), 2);
+            assert_eq!(allocator.peak_bytes(), 150);
+
+            realloc(&allocator, a, 0, 0);
+            realloc(&allocator, b, 0, 16);
+            assert_eq!(allocator.live_bytes(), 0);
+            assert_eq!(allocator.live_allocations(), 0);
+            assert_eq!(allocator.peak_bytes(), 150);
+
+            allocator.reset_peak();
+            assert_eq!(allocator.peak_bytes(), 0);
+
+            // Freeing null is a no-op.
+            assert!(realloc(&allocator, ptr::null_mut(), 0, 0).is_null());
+            assert_eq!(allocator.live_allocations(), 0);
+        }
+    }
+
+}
@@ -0,0 +1,731 @@
+// Copyright (c) 2015-2016, Johan Sköld.
+// License: http://opensource.org/licenses/ISC
+
+//! Hooks for receiving events from bgfx.
+//!
+//! Implement [`Callback`] and pass it to [`bgfx::init`] to be notified of fatal errors, receive
+//! trace output, back the shader cache, and receive screen shots and frame captures.
+//!
+//! [`Callback`]: trait.Callback.html
+//! [`bgfx::init`]: ../fn.init.html
+
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::io::{self, Write};
+use std::os::raw::{c_char, c_int, c_void};
+use std::panic::{self, AssertUnwindSafe};
+use std::process;
+use std::slice;
+use std::sync::{Arc, Mutex};
+
+use bgfx_sys;
+
+use {BgfxError, TextureFormat};
+use screenshot::{Screenshot, ScreenshotTarget};
+
+/// Fatal error codes, as reported to [`Callback::fatal`].
+///
+/// [`Callback::fatal`]: trait.Callback.html#method.fatal
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum Fatal {
+    DebugCheck,
+    InvalidShader,
+    UnableToInitialize,
+    UnableToCreateTexture,
+    DeviceLost,
+
+    /// Code this version of the crate doesn't know about, such as one added by a newer bgfx.
+    Unknown(i32),
+}
+
+impl Fatal {
+
+    /// Whether bgfx can keep running after reporting this error.
+    ///
+    /// `DebugCheck` is a failed internal check in debug builds of bgfx, `InvalidShader` only
+    /// leaves the offending shader unusable, and `DeviceLost` is recovered from by recreating
+    /// resources. bgfx cannot survive `UnableToInitialize` or `UnableToCreateTexture`, and since
+    /// nothing is known about `Unknown` codes, they are not considered recoverable either.
+    #[inline]
+    pub fn is_recoverable(self) -> bool {
+        match self {
+            Fatal::DebugCheck | Fatal::InvalidShader | Fatal::DeviceLost => true,
+            Fatal::UnableToInitialize | Fatal::UnableToCreateTexture | Fatal::Unknown(_) => false,
+        }
+    }
+
+    fn from_i32(n: i32) -> Fatal {
+        match n {
+            bgfx_sys::BGFX_FATAL_DEBUG_CHECK => Fatal::DebugCheck,
+            bgfx_sys::BGFX_FATAL_INVALID_SHADER => Fatal::InvalidShader,
+            bgfx_sys::BGFX_FATAL_UNABLE_TO_INITIALIZE => Fatal::UnableToInitialize,
+            bgfx_sys::BGFX_FATAL_UNABLE_TO_CREATE_TEXTURE => Fatal::UnableToCreateTexture,
+            bgfx_sys::BGFX_FATAL_DEVICE_LOST => Fatal::DeviceLost,
+            n => Fatal::Unknown(n),
+        }
+    }
+
+}
+
+/// Receives events from bgfx.
+///
+/// These functions may be called from both the main thread and the render thread, hence the
+/// `Send + Sync` requirement. Every function has a default implementation, so only the events of
+/// interest need to be implemented.
+///
+/// A panic inside any of these functions aborts the process, as it is not allowed to unwind into
+/// bgfx.
+pub trait Callback: Send + Sync {
+
+    /// Called when bgfx encounters an error. Unless `code` is `Fatal::DebugCheck`, the error is
+    /// also queued, to be returned from [`Bgfx::take_errors`] on the API thread.
+    ///
+    /// It is not safe to continue after most errors, so once this returns, the process is aborted
+    /// unless [`continue_after_fatal`] returns `true`.
+    ///
+    /// The default implementation prints the message.
+    ///
+    /// [`Bgfx::take_errors`]: ../struct.Bgfx.html#method.take_errors
+    /// [`continue_after_fatal`]: #method.continue_after_fatal
+    fn fatal(&self, code: Fatal, message: &str) {
+        eprintln!("bgfx fatal error ({:?}): {}", code, message);
+    }
+
+    /// Returns whether bgfx may keep running after reporting `code` to [`fatal`]. Returning
+    /// `false` aborts the process.
+    ///
+    /// The default implementation returns [`Fatal::is_recoverable`], continuing only after
+    /// `DebugCheck`, `InvalidShader` and `DeviceLost`. Returning `true` for any other code is at
+    /// your own risk: after `UnableToInitialize`, for example, bgfx crashes on its next call.
+    ///
+    /// [`fatal`]: #method.fatal
+    /// [`Fatal::is_recoverable`]: enum.Fatal.html#method.is_recoverable
+    fn continue_after_fatal(&self, code: Fatal) -> bool {
+        code.is_recoverable()
+    }
+
+    /// Called with debug trace output. `message` has already been formatted, and is truncated if
+    /// it is longer than 4 KiB.
+    fn trace_vargs(&self, _file_path: &str, _line: u16, _message: &str) {}
+
+    /// Returns the size of the cached item with the given id, or 0 if it is not in the cache.
+    fn cache_read_size(&self, _id: u64) -> u32 {
+        0
+    }
+
+    /// Fills `data` with the cached item with the given id. Returns whether it was found.
+    fn cache_read(&self, _id: u64, _data: &mut [u8]) -> bool {
+        false
+    }
+
+    /// Stores `data` in the cache under the given id.
+    fn cache_write(&self, _id: u64, _data: &[u8]) {}
+
+    /// Called with the contents of a screen shot requested for `file_path`. The image data is in
+    /// BGRA8 format.
+    fn screen_shot(&self,
+                   _file_path: &str,
+                   _width: u32,
+                   _height: u32,
+                   _pitch: u32,
+                   _data: &[u8],
+                   _yflip: bool) {
+    }
+
+    /// Called when video capture begins.
+    fn capture_begin(&self,
+                     _width: u32,
+                     _height: u32,
+                     _pitch: u32,
+                     _format: TextureFormat,
+                     _yflip: bool) {
+    }
+
+    /// Called when video capture ends.
+    fn capture_end(&self) {}
+
+    /// Called with the contents of each captured frame.
+    fn capture_frame(&self, _data: &[u8]) {}
+
+}
+
+impl<C: Callback + ?Sized> Callback for Arc<C> {
+
+    #[inline]
+    fn fatal(&self, code: Fatal, message: &str) {
+        (**self).fatal(code, message)
+    }
+
+    #[inline]
+    fn continue_after_fatal(&self, code: Fatal) -> bool {
+        (**self).continue_after_fatal(code)
+    }
+
+    #[inline]
+    fn trace_vargs(&self, file_path: &str, line: u16, message: &str) {
+        (**self).trace_vargs(file_path, line, message)
+    }
+
+    #[inline]
+    fn cache_read_size(&self, id: u64) -> u32 {
+        (**self).cache_read_size(id)
+    }
+
+    #[inline]
+    fn cache_read(&self, id: u64, data: &mut [u8]) -> bool {
+        (**self).cache_read(id, data)
+    }
+
+    #[inline]
+    fn cache_write(&self, id: u64, data: &[u8]) {
+        (**self).cache_write(id, data)
+    }
+
+    #[inline]
+    fn screen_shot(&self,
+                   file_path: &str,
+                   width: u32,
+                   height: u32,
+                   pitch: u32,
+                   data: &[u8],
+                   yflip: bool) {
+        (**self).screen_shot(file_path, width, height, pitch, data, yflip)
+    }
+
+    #[inline]
+    fn capture_begin(&self,
+                     width: u32,
+                     height: u32,
+                     pitch: u32,
+                     format: TextureFormat,
+                     yflip: bool) {
+        (**self).capture_begin(width, height, pitch, format, yflip)
+    }
+
+    #[inline]
+    fn capture_end(&self) {
+        (**self).capture_end()
+    }
+
+    #[inline]
+    fn capture_frame(&self, data: &[u8]) {
+        (**self).capture_frame(data)
+    }
+
+}
+
+/// Callback handling some events itself, and passing all others on to an inner callback.
+///
+/// Every `Layer` is a [`Callback`]. The events a layer doesn't override are forwarded to
+/// [`inner`], so the layers in this crate pick up new events without changes.
+///
+/// [`Callback`]: trait.Callback.html
+/// [`inner`]: #tymethod.inner
+pub(crate) trait Layer: Send + Sync {
+
+    /// Callback the events this layer doesn't handle are passed to.
+    fn inner(&self) -> &dyn Callback;
+
+    #[inline]
+    fn fatal(&self, code: Fatal, message: &str) {
+        self.inner().fatal(code, message)
+    }
+
+    #[inline]
+    fn continue_after_fatal(&self, code: Fatal) -> bool {
+        self.inner().continue_after_fatal(code)
+    }
+
+    #[inline]
+    fn trace_vargs(&self, file_path: &str, line: u16, message: &str) {
+        self.inner().trace_vargs(file_path, line, message)
+    }
+
+    #[inline]
+    fn cache_read_size(&self, id: u64) -> u32 {
+        self.inner().cache_read_size(id)
+    }
+
+    #[inline]
+    fn cache_read(&self, id: u64, data: &mut [u8]) -> bool {
+        self.inner().cache_read(id, data)
+    }
+
+    #[inline]
+    fn cache_write(&self, id: u64, data: &[u8]) {
+        self.inner().cache_write(id, data)
+    }
+
+    #[inline]
+    fn screen_shot(&self,
+                   file_path: &str,
+                   width: u32,
+                   height: u32,
+                   pitch: u32,
+                   data: &[u8],
+                   yflip: bool) {
+        self.inner().screen_shot(file_path, width, height, pitch, data, yflip)
+    }
+
+    #[inline]
+    fn capture_begin(&self,
+                     width: u32,
+                     height: u32,
+                     pitch: u32,
+                     format: TextureFormat,
+                     yflip: bool) {
+        self.inner().capture_begin(width, height, pitch, format, yflip)
+    }
+
+    #[inline]
+    fn capture_end(&self) {
+        self.inner().capture_end()
+    }
+
+    #[inline]
+    fn capture_frame(&self, data: &[u8]) {
+        self.inner().capture_frame(data)
+    }
+
+}
+
+impl<L: Layer> Callback for L {
+
+    #[inline]
+    fn fatal(&self, code: Fatal, message: &str) {
+        Layer::fatal(self, code, message)
+    }
+
+    #[inline]
+    fn continue_after_fatal(&self, code: Fatal) -> bool {
+        Layer::continue_after_fatal(self, code)
+    }
+
+    #[inline]
+    fn trace_vargs(&self, file_path: &str, line: u16, message: &str) {
+        Layer::trace_vargs(self, file_path, line, message)
+    }
+
+    #[inline]
+    fn cache_read_size(&self, id: u64) -> u32 {
+        Layer::cache_read_size(self, id)
+    }
+
+    #[inline]
+    fn cache_read(&self, id: u64, data: &mut [u8]) -> bool {
+        Layer::cache_read(self, id, data)
+    }
+
+    #[inline]
+    fn cache_write(&self, id: u64, data: &[u8]) {
+        Layer::cache_write(self, id, data)
+    }
+
+    #[inline]
+    fn screen_shot(&self,
+                   file_path: &str,
+                   width: u32,
+                   height: u32,
+                   pitch: u32,
+                   data: &[u8],
+                   yflip: bool) {
+        Layer::screen_shot(self, file_path, width, height, pitch, data, yflip)
+    }
+
+    #[inline]
+    fn capture_begin(&self,
+                     width: u32,
+                     height: u32,
+                     pitch: u32,
+                     format: TextureFormat,
+                     yflip: bool) {
+        Layer::capture_begin(self, width, height, pitch, format, yflip)
+    }
+
+    #[inline]
+    fn capture_end(&self) {
+        Layer::capture_end(self)
+    }
+
+    #[inline]
+    fn capture_frame(&self, data: &[u8]) {
+        Layer::capture_frame(self, data)
+    }
+
+}
+
+/// Size of the buffer trace messages are formatted into.
+const TRACE_BUFFER_SIZE: usize = 4096;
+
+#[cfg_attr(target_env = "msvc", link(name = "legacy_stdio_definitions"))]
+extern "C" {
+    fn vsnprintf(buffer: *mut c_char,
+                 size: usize,
+                 format: *const c_char,
+                 args: bgfx_sys::va_list)
+                 -> c_int;
+}
+
+/// The callback interface handed to bgfx.
+///
+/// bgfx only knows about the leading `interface` field, and passes a pointer to it back into each
+/// vtable function. Since the struct is `repr(C)`, that pointer can be cast back to the shim.
+#[repr(C)]
+pub(crate) struct CallbackShim {
+    interface: bgfx_sys::bgfx_callback_interface_t,
+    callback: Box<dyn Callback>,
+    screenshots: Mutex<Screenshots>,
+    errors: Mutex<Vec<BgfxError>>,
+}
+
+// The interface only points to a static vtable, and the other fields are `Send + Sync` anyway.
+unsafe impl Send for CallbackShim {}
+unsafe impl Sync for CallbackShim {}
+
+/// Number of frames after which a screen shot request bgfx hasn't answered is dropped.
+const SCREENSHOT_TIMEOUT: u32 = 8;
+
+/// Screen shots requested through `Bgfx::request_screenshot`, keyed by the path handed to bgfx.
+#[derive(Default)]
+struct Screenshots {
+    pending: HashMap<String, PendingScreenshot>,
+    next_id: u64,
+}
+
+struct PendingScreenshot {
+    target: ScreenshotTarget,
+
+    /// Frame the screen shot was requested in.
+    frame: u32,
+}
+
+/// Callback used when none is passed to `init`, behaving like bgfx' built-in callback.
+pub(crate) struct DefaultCallback;
+
+impl Callback for DefaultCallback {
+
+    fn trace_vargs(&self, file_path: &str, line: u16, message: &str) {
+        // Trace output is best effort, so errors writing it are ignored.
+        let stdout = io::stdout();
+        let mut stdout = stdout.lock();
+        let _ = write!(stdout, "{} ({}): {}", file_path, line, message);
+        let _ = stdout.flush();
+    }
+
+}
+
+impl CallbackShim {
+
+    pub(crate) fn new(callback: Option<Box<dyn Callback>>) -> Box<CallbackShim> {
+        Box::new(CallbackShim {
+            interface: bgfx_sys::bgfx_callback_interface_t { vtbl: &CALLBACK_VTBL },
+            callback: callback.unwrap_or_else(|| Box::new(DefaultCallback)),
+            screenshots: Default::default(),
+            errors: Default::default(),
+        })
+    }
+
+    /// Returns the pointer to pass to `bgfx_init`. It remains valid for as long as the box lives.
+    pub(crate) fn as_ptr(&mut self) -> *mut bgfx_sys::bgfx_callback_interface_t {
+        &mut self.interface
+    }
+
+    /// Registers a screen shot target requested in `frame`, and returns the path to request the
+    /// screen shot with.
+    pub(crate) fn add_screenshot(&self, target: ScreenshotTarget, frame: u32) -> String {
+        let mut screenshots = self.screenshots.lock().unwrap_or_else(|e| e.into_inner());
+        let key = format!("bgfx-rs-screenshot-{}", screenshots.next_id);
+        screenshots.next_id += 1;
+        screenshots.pending.insert(key.clone(), PendingScreenshot { target: target, frame: frame });
+        key
+    }
+
+    fn take_screenshot(&self, key: &str) -> Option<ScreenshotTarget> {
+        let mut screenshots = self.screenshots.lock().unwrap_or_else(|e| e.into_inner());
+        screenshots.pending.remove(key).map(|pending| pending.target)
+    }
+
+    /// Drops the screen shot requests bgfx hasn't answered within `SCREENSHOT_TIMEOUT` frames of
+    /// `frame`, such as requests for a frame buffer that was destroyed in the meantime.
+    pub(crate) fn expire_screenshots(&self, frame: u32) {
+        let expired: Vec<PendingScreenshot> = {
+            let mut screenshots = self.screenshots.lock().unwrap_or_else(|e| e.into_inner());
+            if screenshots.pending.is_empty() {
+                return;
+            }
+
+            let keys: Vec<String> = screenshots.pending
+                .iter()
+                .filter(|&(_, pending)| frame.wrapping_sub(pending.frame) > SCREENSHOT_TIMEOUT)
+                .map(|(key, _)| key.clone())
+                .collect();
+
+            keys.into_iter().filter_map(|key| screenshots.pending.remove(&key)).collect()
+        };
+
+        // Targets are dropped outside the lock, since dropping a closure runs arbitrary code.
+        for pending in expired {
+            self.push_error(pending.target.lost());
+        }
+    }
+
+    /// Queues a fatal error for `take_errors`. Debug checks are not queued, since they may fire
+    /// every frame and would pile up if the errors are never taken.
+    fn push_fatal(&self, code: Fatal, message: &str) {
+        if code != Fatal::DebugCheck {
+            self.push_error(BgfxError::Fatal { code: code, message: message.to_owned() });
+        }
+    }
+
+    /// Queues an error for `take_errors`.
+    fn push_error(&self, error: BgfxError) {
+        let mut errors = self.errors.lock().unwrap_or_else(|e| e.into_inner());
+        errors.push(error);
+    }
+
+    /// Takes the errors reported so far.
+    pub(crate) fn take_errors(&self) -> Vec<BgfxError> {
+        let mut errors = self.errors.lock().unwrap_or_else(|e| e.into_inner());
+        ::std::mem::take(&mut *errors)
+    }
+
+}
+
+static CALLBACK_VTBL: bgfx_sys::bgfx_callback_vtbl_t = bgfx_sys::bgfx_callback_vtbl_t {
+    fatal: Some(shim_fatal),
+    trace_vargs: Some(shim_trace_vargs),
+    cache_read_size: Some(shim_cache_read_size),
+    cache_read: Some(shim_cache_read),
+    cache_write: Some(shim_cache_write),
+    screen_shot: Some(shim_screen_shot),
+    capture_begin: Some(shim_capture_begin),
+    capture_end: Some(shim_capture_end),
+    capture_frame: Some(shim_capture_frame),
+};
+
+/// Runs `f`, aborting if it panics.
+fn guard<R, F>(f: F) -> R
+    where F: FnOnce() -> R
+{
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(result) => result,
+        Err(_) => process::abort(),
+    }
+}
+
+/// Runs `f` against the callback behind `this`, aborting if it panics.
+unsafe fn dispatch<R, F>(this: *mut bgfx_sys::bgfx_callback_interface_t, f: F) -> R
+    where F: FnOnce(&dyn Callback) -> R
+{
+    let shim = &*(this as *const CallbackShim);
+    guard(|| f(&*shim.callback))
+}
+
+unsafe fn to_str<'a>(s: *const c_char) -> ::std::borrow::Cow<'a, str> {
+    if s.is_null() { "".into() } else { CStr::from_ptr(s).to_string_lossy() }
+}
+
+unsafe fn to_slice<'a>(data: *const c_void, size: u32) -> &'a [u8] {
+    if data.is_null() { &[] } else { slice::from_raw_parts(data as *const u8, size as usize) }
+}
+
+unsafe extern "C" fn shim_fatal(this: *mut bgfx_sys::bgfx_callback_interface_t,
+                                code: bgfx_sys::bgfx_fatal_t,
+                                message: *const c_char) {
+    let shim = &*(this as *const CallbackShim);
+
+    let resume = guard(|| {
+        let message = to_str(message);
+        let code = Fatal::from_i32(code);
+        shim.push_fatal(code, &message);
+        shim.callback.fatal(code, &message);
+        shim.callback.continue_after_fatal(code)
+    });
+
+    if !resume {
+        process::abort();
+    }
+}
+
+unsafe extern "C" fn shim_trace_vargs(this: *mut bgfx_sys::bgfx_callback_interface_t,
+                                      file_path: *const c_char,
+                                      line: u16,
+                                      format: *const c_char,
+                                      args: bgfx_sys::va_list) {
+    let mut buffer = [0u8; TRACE_BUFFER_SIZE];
+    vsnprintf(buffer.as_mut_ptr() as *mut c_char, buffer.len(), format, args);
+
+    let message = CStr::from_ptr(buffer.as_ptr() as *const c_char).to_string_lossy();
+    let file_path = to_str(file_path);
+    dispatch(this, |cb| cb.trace_vargs(&file_path, line, &message))
+}
+
+unsafe extern "C" fn shim_cache_read_size(this: *mut bgfx_sys::bgfx_callback_interface_t,
+                                          id: u64)
+                                          -> u32 {
+    dispatch(this, |cb| cb.cache_read_size(id))
+}
+
+unsafe extern "C" fn shim_cache_read(this: *mut bgfx_sys::bgfx_callback_interface_t,
+                                     id: u64,
+                                     data: *mut c_void,
+                                     size: u32)
+                                     -> bool {
+    if data.is_null() {
+        return false;
+    }
+
+    let data = slice::from_raw_parts_mut(data as *mut u8, size as usize);
+    dispatch(this, |cb| cb.cache_read(id, data))
+}
+
+unsafe extern "C" fn shim_cache_write(this: *mut bgfx_sys::bgfx_callback_interface_t,
+                                      id: u64,
+                                      data: *const c_void,
+                                      size: u32) {
+    let data = to_slice(data, size);
+    dispatch(this, |cb| cb.cache_write(id, data))
+}
+
+unsafe extern "C" fn shim_screen_shot(this: *mut bgfx_sys::bgfx_callback_interface_t,
+                                      file_path: *const c_char,
+                                      width: u32,
+                                      height: u32,
+                                      pitch: u32,
+                                      data: *const c_void,
+                                      size: u32,
+                                      yflip: bool) {
+    let file_path = to_str(file_path);
+    let data = to_slice(data, size);
+    let shim = &*(this as *const CallbackShim);
+
+    match shim.take_screenshot(&file_path) {
+        Some(target) => {
+            let screenshot = Screenshot {
+                width: width,
+                height: height,
+                pitch: pitch,
+                data: data.to_vec(),
+                yflip: yflip,
+            };
+
+            guard(|| {
+                if let Err(error) = target.deliver(screenshot) {
+                    shim.push_error(error);
+                }
+            });
+        }
+        None => dispatch(this, |cb| cb.screen_shot(&file_path, width, height, pitch, data, yflip)),
+    }
+}
+
+unsafe extern "C" fn shim_capture_begin(this: *mut bgfx_sys::bgfx_callback_interface_t,
+                                        width: u32,
+                                        height: u32,
+                                        pitch: u32,
+                                        format: bgfx_sys::bgfx_texture_format_t,
+                                        yflip: bool) {
+    let format = TextureFormat::from_i32(format).unwrap_or(TextureFormat::Unknown);
+    dispatch(this, |cb| cb.capture_begin(width, height, pitch, format, yflip))
+}
+
+unsafe extern "C" fn shim_capture_end(this: *mut bgfx_sys::bgfx_callback_interface_t) {
+    dispatch(this, |cb| cb.capture_end())
+}
+
+unsafe extern "C" fn shim_capture_frame(this: *mut bgfx_sys::bgfx_callback_interface_t,
+                                        data: *const c_void,
+                                        size: u32) {
+    let data = to_slice(data, size);
+    dispatch(this, |cb| cb.capture_frame(data))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn shims_keep_bgfx_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<::Bgfx>();
+    }
+
+    #[test]
+    fn fatal_from_i32() {
+        assert_eq!(Fatal::from_i32(bgfx_sys::BGFX_FATAL_DEBUG_CHECK), Fatal::DebugCheck);
+        assert_eq!(Fatal::from_i32(bgfx_sys::BGFX_FATAL_DEVICE_LOST), Fatal::DeviceLost);
+        assert_eq!(Fatal::from_i32(bgfx_sys::BGFX_FATAL_COUNT),
+                   Fatal::Unknown(bgfx_sys::BGFX_FATAL_COUNT));
+        assert_eq!(Fatal::from_i32(-1), Fatal::Unknown(-1));
+    }
+
+    #[test]
+    fn only_survivable_errors_are_recoverable() {
+        assert!(Fatal::DebugCheck.is_recoverable());
+        assert!(Fatal::InvalidShader.is_recoverable());
+        assert!(Fatal::DeviceLost.is_recoverable());
+        assert!(!Fatal::UnableToInitialize.is_recoverable());
+        assert!(!Fatal::UnableToCreateTexture.is_recoverable());
+        assert!(!Fatal::Unknown(-1).is_recoverable());
+        assert!(!DefaultCallback.continue_after_fatal(Fatal::UnableToInitialize));
+    }
+
+    #[test]
+    fn debug_checks_are_not_queued() {
+        let shim = CallbackShim::new(None);
+        shim.push_fatal(Fatal::DebugCheck, "check");
+        shim.push_fatal(Fatal::InvalidShader, "shader");
+        shim.push_fatal(Fatal::DebugCheck, "check");
+        shim.push_fatal(Fatal::DeviceLost, "lost");
+        shim.push_fatal(Fatal::Unknown(-1), "unknown");
+
+        let codes: Vec<(Fatal, String)> = shim.take_errors()
+            .into_iter()
+            .map(|error| match error {
+                BgfxError::Fatal { code, message } => (code, message),
+                error => panic!("unexpected error {:?}", error),
+            })
+            .collect();
+        assert_eq!(codes,
+                   [(Fatal::InvalidShader, "shader".to_owned()),
+                    (Fatal::DeviceLost, "lost".to_owned()),
+                    (Fatal::Unknown(-1), "unknown".to_owned())]);
+        assert!(shim.take_errors().is_empty());
+    }
+
+    #[test]
+    fn unanswered_screenshots_expire() {
+        let shim = CallbackShim::new(None);
+        let pending = || shim.screenshots.lock().unwrap().pending.len();
+
+        let old = shim.add_screenshot(ScreenshotTarget::closure(|_| panic!("never taken")), 10);
+        let png = shim.add_screenshot("shot.png".into(), 10);
+        let new = shim.add_screenshot(ScreenshotTarget::closure(|_| {}), 15);
+        assert_ne!(old, new);
+
+        shim.expire_screenshots(10 + SCREENSHOT_TIMEOUT);
+        assert_eq!(pending(), 3);
+        assert!(shim.take_errors().is_empty());
+
+        shim.expire_screenshots(11 + SCREENSHOT_TIMEOUT);
+        assert_eq!(pending(), 1);
+        assert!(shim.take_screenshot(&old).is_none());
+        assert!(shim.take_screenshot(&png).is_none());
+        assert!(shim.take_screenshot(&new).is_some());
+        assert_eq!(pending(), 0);
+
+        let mut lost: Vec<Option<PathBuf>> = shim.take_errors()
+            .into_iter()
+            .map(|error| match error {
+                BgfxError::ScreenshotLost { path } => path,
+                error => panic!("unexpected error {:?}", error),
+            })
+            .collect();
+        lost.sort();
+        assert_eq!(lost, [None, Some(PathBuf::from("shot.png"))]);
+    }
+
+}
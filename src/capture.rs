@@ -0,0 +1,502 @@
+// Copyright (c) 2015-2016, Johan Sköld.
+// License: http://opensource.org/licenses/ISC
+
+//! Video capture.
+//!
+//! When bgfx is reset with `RESET_CAPTURE`, every frame finished through
+//! [`Bgfx::frame_capture`] is passed to the capture callbacks. [`CaptureRecorder`] implements
+//! those callbacks and streams the frames into a [`CaptureSink`], such as [`Y4mWriter`].
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//!
+//! let writer = bgfx::Y4mWriter::create("capture.y4m", 60).unwrap();
+//! let recorder = Arc::new(bgfx::CaptureRecorder::new(writer));
+//! let bgfx = bgfx::init(bgfx::RendererType::Default,
+//!                       None,
+//!                       None,
+//!                       Some(Box::new(recorder.clone())),
+//!                       None)
+//!     .expect("Failed to initialize bgfx");
+//!
+//! bgfx.reset(1280, 720, bgfx::RESET_CAPTURE);
+//! loop {
+//!     // ...
+//!     bgfx.frame_capture();
+//!
+//!     if let Some(err) = recorder.take_error() {
+//!         panic!("Capture failed: {}", err);
+//!     }
+//! }
+//! ```
+//!
+//! [`Bgfx::frame_capture`]: ../struct.Bgfx.html#method.frame_capture
+//! [`CaptureRecorder`]: struct.CaptureRecorder.html
+//! [`CaptureSink`]: trait.CaptureSink.html
+//! [`Y4mWriter`]: struct.Y4mWriter.html
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use {Callback, TextureFormat};
+use callback::DefaultCallback;
+
+/// Receives captured frames from a [`CaptureRecorder`].
+///
+/// [`CaptureRecorder`]: struct.CaptureRecorder.html
+pub trait CaptureSink: Send {
+
+    /// Called when capturing begins. Frames are `height` rows of `pitch` bytes each, in the given
+    /// format. If `yflip` is set, the rows are stored bottom to top.
+    fn begin(&mut self,
+             width: u32,
+             height: u32,
+             pitch: u32,
+             format: TextureFormat,
+             yflip: bool)
+             -> io::Result<()>;
+
+    /// Called with the contents of each captured frame.
+    fn frame(&mut self, data: &[u8]) -> io::Result<()>;
+
+    /// Called when capturing ends.
+    fn end(&mut self) -> io::Result<()>;
+
+}
+
+/// [`Callback`] streaming captured frames into a [`CaptureSink`].
+///
+/// Errors reported by the sink stop the capture until the next time it begins, and can be
+/// retrieved through [`take_error`]. Events other than captures go to the callback given to
+/// [`with_callback`], or are handled the way they are without a callback.
+///
+/// [`Callback`]: ../callback/trait.Callback.html
+/// [`CaptureSink`]: trait.CaptureSink.html
+/// [`with_callback`]: #method.with_callback
+/// [`take_error`]: #method.take_error
+pub struct CaptureRecorder<S: CaptureSink> {
+    sink: Mutex<SinkState<S>>,
+    callback: Box<dyn Callback>,
+    frames: AtomicUsize,
+}
+
+struct SinkState<S> {
+    sink: S,
+    failed: bool,
+    error: Option<io::Error>,
+}
+
+impl<S: CaptureSink> SinkState<S> {
+
+    fn run<F>(&mut self, f: F)
+        where F: FnOnce(&mut S) -> io::Result<()>
+    {
+        if self.failed {
+            return;
+        }
+
+        if let Err(err) = f(&mut self.sink) {
+            self.failed = true;
+            self.error = Some(err);
+        }
+    }
+
+}
+
+impl<S: CaptureSink> CaptureRecorder<S> {
+
+    /// Creates a recorder streaming into `sink`.
+    #[inline]
+    pub fn new(sink: S) -> CaptureRecorder<S> {
+        CaptureRecorder {
+            sink: Mutex::new(SinkState { sink: sink, failed: false, error: None }),
+            callback: Box::new(DefaultCallback),
+            frames: AtomicUsize::new(0),
+        }
+    }
+
+    /// Creates a recorder streaming into `sink`, and forwarding all other callbacks to
+    /// `callback`.
+    #[inline]
+    pub fn with_callback(sink: S, callback: Box<dyn Callback>) -> CaptureRecorder<S> {
+        CaptureRecorder { callback: callback, ..CaptureRecorder::new(sink) }
+    }
+
+    /// Number of frames passed to the sink so far.
+    ///
+    /// bgfx begins a new capture on every reset with `RESET_CAPTURE`, such as when the window is
+    /// resized. The count keeps going across those captures.
+    #[inline]
+    pub fn frames(&self) -> usize {
+        self.frames.load(Ordering::Relaxed)
+    }
+
+    /// Takes the error that stopped the capture, if any.
+    pub fn take_error(&self) -> Option<io::Error> {
+        self.state().error.take()
+    }
+
+    fn state(&self) -> MutexGuard<'_, SinkState<S>> {
+        self.sink.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+}
+
+impl<S: CaptureSink> ::callback::Layer for CaptureRecorder<S> {
+
+    #[inline]
+    fn inner(&self) -> &dyn Callback {
+        &*self.callback
+    }
+
+    fn capture_begin(&self,
+                     width: u32,
+                     height: u32,
+                     pitch: u32,
+                     format: TextureFormat,
+                     yflip: bool) {
+        let mut state = self.state();
+        state.failed = false;
+        state.run(|sink| sink.begin(width, height, pitch, format, yflip));
+    }
+
+    fn capture_end(&self) {
+        self.state().run(|sink| sink.end());
+    }
+
+    fn capture_frame(&self, data: &[u8]) {
+        let mut state = self.state();
+        state.run(|sink| sink.frame(data));
+
+        if !state.failed {
+            self.frames.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+}
+
+/// [`CaptureSink`] writing an uncompressed YUV4MPEG2 (Y4M) video stream.
+///
+/// Frames are converted from BGRA8 or RGBA8 to YUV 4:2:0 with full range BT.601 coefficients.
+/// The resulting file can be played or encoded by most video tools, such as ffmpeg.
+///
+/// The stream header is written when the first capture begins. bgfx begins a new capture on
+/// every reset with `RESET_CAPTURE`, and later captures continue the same stream. Since a Y4M
+/// stream has a single frame size, a capture of a different size fails with
+/// `io::ErrorKind::InvalidInput`.
+///
+/// [`CaptureSink`]: trait.CaptureSink.html
+pub struct Y4mWriter<W: Write + Send> {
+    writer: W,
+    fps: u32,
+    layout: Option<FrameLayout>,
+    size: Option<(u32, u32)>,
+    frame: Vec<u8>,
+}
+
+#[derive(Copy, Clone)]
+struct FrameLayout {
+    width: usize,
+    height: usize,
+    pitch: usize,
+    bgra: bool,
+    yflip: bool,
+}
+
+impl Y4mWriter<BufWriter<File>> {
+
+    /// Creates a buffered writer producing a file at `path`, at the given frame rate.
+    pub fn create<P: AsRef<Path>>(path: P, fps: u32) -> io::Result<Y4mWriter<BufWriter<File>>> {
+        Ok(Y4mWriter::new(BufWriter::new(File::create(path)?), fps))
+    }
+
+}
+
+impl<W: Write + Send> Y4mWriter<W> {
+
+    /// Creates a writer producing a stream at the given frame rate.
+    #[inline]
+    pub fn new(writer: W, fps: u32) -> Y4mWriter<W> {
+        Y4mWriter { writer: writer, fps: fps, layout: None, size: None, frame: Vec::new() }
+    }
+
+    /// Returns the underlying writer.
+    #[inline]
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Converts a frame to planar YUV 4:2:0 into `self.frame`.
+    fn convert(&mut self, layout: FrameLayout, data: &[u8]) {
+        let FrameLayout { width, height, pitch, bgra, yflip } = layout;
+        let chroma_width = width.div_ceil(2);
+        let chroma_height = height.div_ceil(2);
+        let luma_size = width * height;
+        let chroma_size = chroma_width * chroma_height;
+
+        self.frame.clear();
+        self.frame.resize(luma_size + 2 * chroma_size, 0);
+        let (luma, chroma) = self.frame.split_at_mut(luma_size);
+        let (cb_plane, cr_plane) = chroma.split_at_mut(chroma_size);
+
+        let pixel = |x: usize, y: usize| -> (f32, f32, f32) {
+            let row = if yflip { height - 1 - y } else { y };
+            let i = row * pitch + x * 4;
+            match data.get(i..i + 3) {
+                Some(p) if bgra => (p[2] as f32, p[1] as f32, p[0] as f32),
+                Some(p) => (p[0] as f32, p[1] as f32, p[2] as f32),
+                None => (0.0, 0.0, 0.0),
+            }
+        };
+
+        for y in 0..height {
+            for x in 0..width {
+                let (r, g, b) = pixel(x, y);
+                luma[y * width + x] = clamp(0.299 * r + 0.587 * g + 0.114 * b);
+            }
+        }
+
+        for cy in 0..chroma_height {
+            for cx in 0..chroma_width {
+                let (mut r, mut g, mut b, mut n) = (0.0, 0.0, 0.0, 0.0);
+                for y in cy * 2..(cy * 2 + 2).min(height) {
+                    for x in cx * 2..(cx * 2 + 2).min(width) {
+                        let (pr, pg, pb) = pixel(x, y);
+                        r += pr;
+                        g += pg;
+                        b += pb;
+                        n += 1.0;
+                    }
+                }
+
+                let (r, g, b) = (r / n, g / n, b / n);
+                let i = cy * chroma_width + cx;
+                cb_plane[i] = clamp(128.0 - 0.168_736 * r - 0.331_264 * g + 0.5 * b);
+                cr_plane[i] = clamp(128.0 + 0.5 * r - 0.418_688 * g - 0.081_312 * b);
+            }
+        }
+    }
+
+}
+
+impl<W: Write + Send> CaptureSink for Y4mWriter<W> {
+
+    fn begin(&mut self,
+             width: u32,
+             height: u32,
+             pitch: u32,
+             format: TextureFormat,
+             yflip: bool)
+             -> io::Result<()> {
+        let bgra = match format {
+            TextureFormat::BGRA8 => true,
+            TextureFormat::RGBA8 => false,
+            _ => {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                          format!("Unsupported capture format {:?}", format)))
+            }
+        };
+
+        match self.size {
+            Some((w, h)) if (w, h) != (width, height) => {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                          format!("Capture size changed from {}x{} to {}x{}",
+                                                  w,
+                                                  h,
+                                                  width,
+                                                  height)))
+            }
+            Some(_) => {}
+            None => {
+                writeln!(self.writer,
+                         "YUV4MPEG2 W{} H{} F{}:1 Ip A1:1 C420jpeg",
+                         width,
+                         height,
+                         self.fps)?;
+                self.size = Some((width, height));
+            }
+        }
+
+        // The pitch, format and orientation only affect how frames are read, so they may change
+        // between captures.
+        self.layout = Some(FrameLayout {
+            width: width as usize,
+            height: height as usize,
+            pitch: pitch as usize,
+            bgra: bgra,
+            yflip: yflip,
+        });
+
+        Ok(())
+    }
+
+    fn frame(&mut self, data: &[u8]) -> io::Result<()> {
+        let layout = match self.layout {
+            Some(layout) => layout,
+            None => return Err(io::Error::other("Capture frame received before capture began")),
+        };
+
+        self.convert(layout, data);
+        self.writer.write_all(b"FRAME\n")?;
+        self.writer.write_all(&self.frame)
+    }
+
+    fn end(&mut self) -> io::Result<()> {
+        self.layout = None;
+        self.writer.flush()
+    }
+
+}
+
+#[inline]
+fn clamp(value: f32) -> u8 {
+    value.round().clamp(0.0, 255.0) as u8
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::io;
+
+    use super::*;
+    use TextureFormat;
+
+    /// Splits a Y4M stream into its header line and frames.
+    fn parse(stream: &[u8], frame_size: usize) -> (String, Vec<Vec<u8>>) {
+        let end = stream.iter().position(|&b| b == b'\n').unwrap();
+        let header = String::from_utf8(stream[..end].to_vec()).unwrap();
+
+        let mut frames = Vec::new();
+        let mut rest = &stream[end + 1..];
+        while !rest.is_empty() {
+            assert_eq!(&rest[..6], b"FRAME\n");
+            frames.push(rest[6..6 + frame_size].to_vec());
+            rest = &rest[6 + frame_size..];
+        }
+        (header, frames)
+    }
+
+    #[test]
+    fn converts_bgra_to_yuv420() {
+        // 3x3 BGRA frame with 4 bytes of row padding: a white column, a black column and a red
+        // column.
+        let white = [255, 255, 255, 255];
+        let black = [0, 0, 0, 255];
+        let red = [0, 0, 255, 255];
+        let mut frame = Vec::new();
+        for _ in 0..3 {
+            frame.extend_from_slice(&white);
+            frame.extend_from_slice(&black);
+            frame.extend_from_slice(&red);
+            frame.extend_from_slice(&[0; 4]);
+        }
+
+        let mut writer = Y4mWriter::new(Vec::new(), 30);
+        writer.begin(3, 3, 16, TextureFormat::BGRA8, false).unwrap();
+        writer.frame(&frame).unwrap();
+        writer.end().unwrap();
+
+        // Odd sizes round the chroma planes up, to 2x2.
+        let (header, frames) = parse(&writer.into_inner(), 9 + 2 * 4);
+        assert_eq!(header, "YUV4MPEG2 W3 H3 F30:1 Ip A1:1 C420jpeg");
+        assert_eq!(frames.len(), 1);
+
+        let (luma, chroma) = frames[0].split_at(9);
+        assert_eq!(luma, [255, 0, 76, 255, 0, 76, 255, 0, 76]);
+
+        // The left chroma samples average white and black, the right ones are pure red.
+        let (cb, cr) = chroma.split_at(4);
+        assert_eq!(cb, [128, 85, 128, 85]);
+        assert_eq!(cr, [128, 255, 128, 255]);
+    }
+
+    #[test]
+    fn yflip_and_rgba() {
+        // 1x2 RGBA frame, stored bottom to top: the top row is red, the bottom row blue.
+        let frame = [0, 0, 255, 255, 255, 0, 0, 255];
+
+        let mut writer = Y4mWriter::new(Vec::new(), 60);
+        writer.begin(1, 2, 4, TextureFormat::RGBA8, true).unwrap();
+        writer.frame(&frame).unwrap();
+
+        let (_, frames) = parse(&writer.into_inner(), 2 + 2);
+        assert_eq!(&frames[0][..2], [76, 29]);
+    }
+
+    #[test]
+    fn header_is_written_once() {
+        let mut writer = Y4mWriter::new(Vec::new(), 25);
+        writer.begin(2, 2, 8, TextureFormat::BGRA8, false).unwrap();
+        writer.frame(&[0; 16]).unwrap();
+        writer.end().unwrap();
+
+        // A later capture of the same size continues the stream, even with another layout.
+        writer.begin(2, 2, 12, TextureFormat::RGBA8, true).unwrap();
+        writer.frame(&[255; 24]).unwrap();
+
+        let err = writer.begin(4, 2, 16, TextureFormat::BGRA8, false).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        let (header, frames) = parse(&writer.into_inner(), 4 + 2);
+        assert_eq!(header, "YUV4MPEG2 W2 H2 F25:1 Ip A1:1 C420jpeg");
+        assert_eq!(frames, [vec![0, 0, 0, 0, 128, 128], vec![255, 255, 255, 255, 128, 128]]);
+    }
+
+    #[test]
+    fn rejects_unsupported_formats_and_early_frames() {
+        let mut writer = Y4mWriter::new(Vec::new(), 30);
+        assert!(writer.frame(&[0; 4]).is_err());
+        assert!(writer.begin(1, 1, 4, TextureFormat::RGBA16F, false).is_err());
+        assert!(writer.into_inner().is_empty());
+    }
+
+    /// Sink failing on the second frame.
+    struct FailingSink {
+        frames: usize,
+    }
+
+    impl CaptureSink for FailingSink {
+
+        fn begin(&mut self, _: u32, _: u32, _: u32, _: TextureFormat, _: bool) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn frame(&mut self, _data: &[u8]) -> io::Result<()> {
+            self.frames += 1;
+            if self.frames == 2 {
+                Err(io::Error::other("disk full"))
+            } else {
+                Ok(())
+            }
+        }
+
+        fn end(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+
+    }
+
+    #[test]
+    fn recorder_stops_on_first_error() {
+        let recorder = CaptureRecorder::new(FailingSink { frames: 0 });
+        recorder.capture_begin(1, 1, 4, TextureFormat::BGRA8, false);
+        for _ in 0..4 {
+            recorder.capture_frame(&[0; 4]);
+        }
+
+        assert_eq!(recorder.frames(), 1);
+        assert_eq!(recorder.take_error().unwrap().to_string(), "disk full");
+        assert!(recorder.take_error().is_none());
+
+        // A new capture starts over, and the count keeps going.
+        recorder.capture_begin(1, 1, 4, TextureFormat::BGRA8, false);
+        recorder.capture_frame(&[0; 4]);
+        assert_eq!(recorder.frames(), 2);
+    }
+
+}
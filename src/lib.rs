@@ -34,7 +34,7 @@
 //!
 //! ```no_run
 //! std::thread::spawn(|| {
-//!     let bgfx = bgfx::init(bgfx::RendererType::Default, None, None)
+//!     let bgfx = bgfx::init(bgfx::RendererType::Default, None, None, None, None)
 //!         .expect("Failed to initialize bgfx");
 //!     // ...
 //! });
@@ -58,21 +58,63 @@
 //! [`bgfx::render_frame`]: fn.render_frame.html
 //! [`PlatformData`]: struct.PlatformData.html
 
+#[cfg(feature = "derive")]
+extern crate bgfx_derive;
 #[macro_use]
 extern crate bgfx_sys;
 #[macro_use]
 extern crate bitflags;
 extern crate libc;
+#[cfg(feature = "log")]
+#[macro_use]
+extern crate log;
 
+use std::cmp;
+use std::collections::HashMap;
 use std::ffi;
 use std::marker::PhantomData;
+use std::io;
 use std::mem;
+use std::path::PathBuf;
 use std::ptr;
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::fmt;
 
+pub mod allocator;
+pub mod callback;
+pub mod capture;
 pub mod flags;
-
+#[cfg(feature = "log")]
+pub mod logger;
+pub mod screenshot;
+pub mod shader_cache;
+pub mod stats;
+pub mod topology;
+pub mod view;
+
+mod vertex;
+
+pub use allocator::{Allocator, CountingAllocator, RustAllocator};
+pub use callback::{Callback, Fatal};
+pub use capture::{CaptureRecorder, CaptureSink, Y4mWriter};
 pub use flags::*;
+#[cfg(feature = "log")]
+pub use logger::LogCallback;
+pub use screenshot::{Screenshot, ScreenshotTarget};
+pub use shader_cache::DiskShaderCache;
+pub use stats::{Stats, StatsHistory, StatsSummary};
+pub use topology::{Index, TopologyConvert, TopologySort};
+pub use vertex::{AttribInfo, Attribs, Vertex};
+#[cfg(feature = "derive")]
+pub use bgfx_derive::Vertex;
+pub use view::View;
+
+use allocator::AllocatorShim;
+use callback::CallbackShim;
+
+/// Maximum number of frame buffer attachments, `BGFX_CONFIG_MAX_FRAME_BUFFER_ATTACHMENTS` in the
+/// default bgfx configuration.
+const MAX_FRAME_BUFFER_ATTACHMENTS: usize = 8;
 
 
 #[repr(u16)]
@@ -237,7 +279,7 @@ impl Default for TextureFormat {
     fn default() -> Self { TextureFormat::Unknown }
 }
 
-/*impl TextureFormat {
+impl TextureFormat {
 
     fn from_i32(n: i32) -> Option<TextureFormat> {
         if n <= bgfx_sys::BGFX_TEXTURE_FORMAT_COUNT {
@@ -248,7 +290,6 @@ impl Default for TextureFormat {
     }
 
 }
-*/
 
 /// `render_frame()` results.
 #[repr(i32)]
@@ -276,6 +317,101 @@ impl RenderFrame {
 
 }
 
+/// Occlusion query results.
+#[repr(i32)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum OcclusionQueryResult {
+    /// The query found the geometry to be fully occluded.
+    Invisible = bgfx_sys::BGFX_OCCLUSION_QUERY_RESULT_INVISIBLE,
+
+    /// The query found the geometry to be at least partially visible.
+    Visible = bgfx_sys::BGFX_OCCLUSION_QUERY_RESULT_VISIBLE,
+
+    /// The query has no result yet.
+    NoResult = bgfx_sys::BGFX_OCCLUSION_QUERY_RESULT_NORESULT,
+}
+
+impl OcclusionQueryResult {
+
+    fn from_i32(n: i32) -> Option<OcclusionQueryResult> {
+        let result = match n {
+            bgfx_sys::BGFX_OCCLUSION_QUERY_RESULT_INVISIBLE => OcclusionQueryResult::Invisible,
+            bgfx_sys::BGFX_OCCLUSION_QUERY_RESULT_VISIBLE => OcclusionQueryResult::Visible,
+            bgfx_sys::BGFX_OCCLUSION_QUERY_RESULT_NORESULT => OcclusionQueryResult::NoResult,
+            _ => return None,
+        };
+        Some(result)
+    }
+
+}
+
+/// Backbuffer ratio, used to size textures and views relative to the backbuffer.
+#[repr(i32)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum BackbufferRatio {
+    /// Equal to the backbuffer.
+    Equal = bgfx_sys::BGFX_BACKBUFFER_RATIO_EQUAL,
+
+    /// One half the size of the backbuffer.
+    Half = bgfx_sys::BGFX_BACKBUFFER_RATIO_HALF,
+
+    /// One quarter the size of the backbuffer.
+    Quarter = bgfx_sys::BGFX_BACKBUFFER_RATIO_QUARTER,
+
+    /// One eighth the size of the backbuffer.
+    Eighth = bgfx_sys::BGFX_BACKBUFFER_RATIO_EIGHTH,
+
+    /// One sixteenth the size of the backbuffer.
+    Sixteenth = bgfx_sys::BGFX_BACKBUFFER_RATIO_SIXTEENTH,
+
+    /// Double the size of the backbuffer.
+    Double = bgfx_sys::BGFX_BACKBUFFER_RATIO_DOUBLE,
+}
+
+impl BackbufferRatio {
+
+    /// Scales a backbuffer size by the ratio.
+    fn scale(self, width: u16, height: u16) -> (u16, u16) {
+        let (width, height) = match self {
+            BackbufferRatio::Equal => (width, height),
+            BackbufferRatio::Half => (width / 2, height / 2),
+            BackbufferRatio::Quarter => (width / 4, height / 4),
+            BackbufferRatio::Eighth => (width / 8, height / 8),
+            BackbufferRatio::Sixteenth => (width / 16, height / 16),
+            BackbufferRatio::Double => (width.saturating_mul(2), height.saturating_mul(2)),
+        };
+
+        (cmp::max(width, 1), cmp::max(height, 1))
+    }
+
+}
+
+/// Side of a cube map.
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum CubeMapSide {
+    PositiveX = bgfx_sys::BGFX_CUBE_MAP_POSITIVE_X,
+    NegativeX = bgfx_sys::BGFX_CUBE_MAP_NEGATIVE_X,
+    PositiveY = bgfx_sys::BGFX_CUBE_MAP_POSITIVE_Y,
+    NegativeY = bgfx_sys::BGFX_CUBE_MAP_NEGATIVE_Y,
+    PositiveZ = bgfx_sys::BGFX_CUBE_MAP_POSITIVE_Z,
+    NegativeZ = bgfx_sys::BGFX_CUBE_MAP_NEGATIVE_Z,
+}
+
+/// Access mode of compute resources.
+#[repr(i32)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum Access {
+    /// Read.
+    Read = bgfx_sys::BGFX_ACCESS_READ,
+
+    /// Write.
+    Write = bgfx_sys::BGFX_ACCESS_WRITE,
+
+    /// Read and write.
+    ReadWrite = bgfx_sys::BGFX_ACCESS_READWRITE,
+}
+
 /// Vertex attribute.
 #[repr(u32)]
 #[derive(PartialEq, Eq, Debug, Copy, Clone)]
@@ -381,6 +517,22 @@ pub enum UniformType {
     Mat4 = bgfx_sys::BGFX_UNIFORM_TYPE_MAT4 as u32,
 }
 
+impl UniformType {
+
+    fn from_i32(n: i32) -> Option<UniformType> {
+        let uniform_type = match n {
+            bgfx_sys::BGFX_UNIFORM_TYPE_INT1 => UniformType::Int1,
+            bgfx_sys::BGFX_UNIFORM_TYPE_END => UniformType::End,
+            bgfx_sys::BGFX_UNIFORM_TYPE_VEC4 => UniformType::Vec4,
+            bgfx_sys::BGFX_UNIFORM_TYPE_MAT3 => UniformType::Mat3,
+            bgfx_sys::BGFX_UNIFORM_TYPE_MAT4 => UniformType::Mat4,
+            _ => return None,
+        };
+        Some(uniform_type)
+    }
+
+}
+
 /// bgfx error.
 #[derive(Debug)]
 pub enum BgfxError {
@@ -392,6 +544,140 @@ pub enum BgfxError {
 
     /// Initialization failed.
     InitFailed,
+
+    /// An access or update went past the end of a resource.
+    OutOfBounds,
+
+    /// A buffer was created from an empty slice, which bgfx can't create a buffer from.
+    EmptyBuffer,
+
+    /// The size of a vertex type does not match the stride of its vertex declaration, or is zero.
+    StrideMismatch {
+        /// Stride of the vertex declaration.
+        expected: usize,
+
+        /// Size of the vertex type.
+        found: usize,
+    },
+
+    /// The renderer lacks the given capabilities.
+    NotSupported(CapsFlags),
+
+    /// A vertex declaration lacks an attribute needed by the operation.
+    MissingAttrib(Attrib),
+
+    /// The renderer doesn't support the texture format for the requested kind of texture.
+    UnsupportedTextureFormat(TextureFormat),
+
+    /// A texture dimension exceeds the renderer's maximum texture size.
+    TextureTooLarge {
+        /// Requested size.
+        size: u32,
+
+        /// Maximum texture size.
+        max: u32,
+    },
+
+    /// A texture operation was used on the wrong kind of texture, such as a cube map update on a
+    /// 2D texture.
+    TextureTypeMismatch,
+
+    /// A row pitch is smaller than a row of the data it describes.
+    InvalidPitch {
+        /// Size of a row, in bytes.
+        min: u32,
+
+        /// Given pitch.
+        found: u16,
+    },
+
+    /// A frame buffer was created without attachments, or with more than bgfx supports.
+    InvalidAttachmentCount {
+        /// Maximum number of attachments.
+        max: usize,
+
+        /// Number of attachments given.
+        found: usize,
+    },
+
+    /// Two textures used together have different sizes.
+    TextureSizeMismatch {
+        /// Width and height of the first texture.
+        expected: (u16, u16),
+
+        /// Width and height of the other texture.
+        found: (u16, u16),
+    },
+
+    /// Two textures used together have different formats.
+    TextureFormatMismatch {
+        /// Format of the destination texture.
+        expected: TextureFormat,
+
+        /// Format of the source texture.
+        found: TextureFormat,
+    },
+
+    /// A texture lacks flags it must have been created with for the operation.
+    MissingTextureFlags(TextureFlags),
+
+    /// The size of a memory buffer does not match the size of the data it should hold.
+    InvalidMemorySize {
+        /// Number of bytes needed.
+        expected: u32,
+
+        /// Size of the memory buffer.
+        found: u32,
+    },
+
+    /// A uniform was set with a value of the wrong type.
+    UniformTypeMismatch {
+        /// Type the uniform was created with.
+        expected: UniformType,
+
+        /// Type of the value.
+        found: UniformType,
+    },
+
+    /// A uniform was set with no values, or with more values than its array can hold.
+    UniformCountMismatch {
+        /// Number of elements the uniform was created with.
+        expected: u16,
+
+        /// Number of values given.
+        found: usize,
+    },
+
+    /// bgfx reported a fatal error through its callback. See [`Bgfx::take_errors`].
+    ///
+    /// [`Bgfx::take_errors`]: struct.Bgfx.html#method.take_errors
+    Fatal {
+        /// Kind of error.
+        code: Fatal,
+
+        /// Message describing the error.
+        message: String,
+    },
+
+    /// A requested screen shot could not be saved. See [`Bgfx::take_errors`].
+    ///
+    /// [`Bgfx::take_errors`]: struct.Bgfx.html#method.take_errors
+    ScreenshotFailed {
+        /// Path of the PNG file.
+        path: PathBuf,
+
+        /// Error writing the file.
+        error: io::Error,
+    },
+
+    /// bgfx never answered a screen shot request. See [`Bgfx::take_errors`].
+    ///
+    /// [`Bgfx::take_errors`]: struct.Bgfx.html#method.take_errors
+    ScreenshotLost {
+        /// Path of the PNG file the screen shot was requested for, or `None` if it was requested
+        /// for a closure.
+        path: Option<PathBuf>,
+    },
 }
 
 /// bgfx-managed buffer of memory.
@@ -438,6 +724,12 @@ impl<'b> Memory<'b> {
         }
     }
 
+    /// Size of the buffer, in bytes.
+    #[inline]
+    fn size(&self) -> u32 {
+        unsafe { (*self.handle).size }
+    }
+
 }
 
 /// Shader program.
@@ -445,8 +737,8 @@ impl<'b> Memory<'b> {
 /// The program holds a vertex shader and a fragment shader.
 pub struct Program<'s> {
     handle: bgfx_sys::bgfx_program_handle_t,
-    _vsh: Shader<'s>,
-    _fsh: Shader<'s>,
+    vsh: Shader<'s>,
+    fsh: Shader<'s>,
 }
 
 impl<'s> Program<'s> {
@@ -457,8 +749,33 @@ impl<'s> Program<'s> {
     pub fn new(vsh: Shader<'s>, fsh: Shader<'s>) -> Program<'s> {
         unsafe {
             let handle = bgfx_sys::bgfx_create_program(vsh.handle, fsh.handle, false);
-            Program { handle: handle, _vsh: vsh, _fsh: fsh }
+            Program { handle: handle, vsh: vsh, fsh: fsh }
+        }
+    }
+
+    /// Gets the uniforms declared by the program's shaders. Uniforms declared by both shaders are
+    /// only listed once.
+    pub fn uniforms(&self) -> Vec<UniformInfo> {
+        let mut uniforms = self.vsh.uniforms();
+
+        for info in self.fsh.uniforms() {
+            if !uniforms.iter().any(|u| u.name == info.name) {
+                uniforms.push(info);
+            }
         }
+
+        uniforms
+    }
+
+    /// Creates a handle for each uniform declared by the program's shaders, keyed by name.
+    pub fn create_uniforms<'u>(&self) -> HashMap<String, UniformHandle<'u>> {
+        self.uniforms()
+            .into_iter()
+            .map(|info| {
+                let handle = UniformHandle::new(&info.name, info.kind, info.num);
+                (info.name, handle)
+            })
+            .collect()
     }
 
 }
@@ -472,6 +789,41 @@ impl<'s> Drop for Program<'s> {
 
 }
 
+/// Compute shader program.
+///
+/// The program holds a compute shader.
+pub struct ComputeProgram<'s> {
+    handle: bgfx_sys::bgfx_program_handle_t,
+    _csh: Shader<'s>,
+}
+
+impl<'s> ComputeProgram<'s> {
+
+    /// Creates a new program from a compute shader. Ownership of the shader is moved to the
+    /// program.
+    ///
+    /// Fails with `BgfxError::NotSupported` if the renderer lacks compute support.
+    #[inline]
+    pub fn new(bgfx: &Bgfx, csh: Shader<'s>) -> Result<ComputeProgram<'s>, BgfxError> {
+        bgfx.require(CAPS_COMPUTE)?;
+
+        unsafe {
+            let handle = bgfx_sys::bgfx_create_compute_program(csh.handle, false);
+            Ok(ComputeProgram { handle: handle, _csh: csh })
+        }
+    }
+
+}
+
+impl<'s> Drop for ComputeProgram<'s> {
+
+    #[inline]
+    fn drop(&mut self) {
+        unsafe { bgfx_sys::bgfx_destroy_program(self.handle) }
+    }
+
+}
+
 /// Shader.
 pub struct Shader<'m> {
     handle: bgfx_sys::bgfx_shader_handle_t,
@@ -489,6 +841,18 @@ impl<'m> Shader<'m> {
         }
     }
 
+    /// Gets the uniforms declared by the shader.
+    pub fn uniforms(&self) -> Vec<UniformInfo> {
+        unsafe {
+            let num = bgfx_sys::bgfx_get_shader_uniforms(self.handle, ptr::null_mut(), 0);
+            let mut handles = vec![bgfx_sys::bgfx_uniform_handle_t { idx: 0 }; num as usize];
+            let num = bgfx_sys::bgfx_get_shader_uniforms(self.handle, handles.as_mut_ptr(), num);
+            handles.truncate(num as usize);
+
+            handles.into_iter().filter_map(|handle| UniformInfo::from_handle(handle)).collect()
+        }
+    }
+
 }
 
 impl<'m> Drop for Shader<'m> {
@@ -501,25 +865,97 @@ impl<'m> Drop for Shader<'m> {
 }
 
 /// Vertex index buffer.
-pub struct IndexBuffer<'m> {
+///
+/// Buffers created from a slice of `u16` or `u32` indices with [`from_slice`] carry their index
+/// type in `I`. Buffers created from untyped memory with [`new`] leave it as `()`.
+///
+/// `I` records what the buffer was created from, and is what [`len`] counts. The index width
+/// itself is fixed by `BUFFER_INDEX32` when the buffer is created, so [`Bgfx::set_index_buffer`]
+/// accepts buffers of any index type, and no check happens when binding.
+///
+/// [`Bgfx::set_index_buffer`]: struct.Bgfx.html#method.set_index_buffer
+/// [`from_slice`]: #method.from_slice
+/// [`len`]: #method.len
+/// [`new`]: #method.new
+pub struct IndexBuffer<'m, I = ()> {
     handle: bgfx_sys::bgfx_index_buffer_handle_t,
-    _phantom: PhantomData<&'m ()>,
+    num: u32,
+    _phantom: PhantomData<(&'m (), I)>,
 }
 
 impl<'m> IndexBuffer<'m> {
 
     /// Creates a new index buffer from bgfx-managed memory.
+    ///
+    /// The indices are 16 bits wide, unless `flags` contains `BUFFER_INDEX32`.
     #[inline]
     pub fn new(indices: Memory<'m>, flags: BufferFlags) -> IndexBuffer<'m> {
+        let size = if flags.contains(BUFFER_INDEX32) { 4 } else { 2 };
+        let num = indices.size() / size;
+
+        unsafe {
+            let handle = bgfx_sys::bgfx_create_index_buffer(indices.handle, flags.bits());
+            IndexBuffer { handle: handle, num: num, _phantom: PhantomData }
+        }
+    }
+
+}
+
+impl<'m, I: Index> IndexBuffer<'m, I> {
+
+    /// Creates a new index buffer holding a copy of `indices`.
+    ///
+    /// `BUFFER_INDEX32` is set or cleared in `flags` to match `I`.
+    ///
+    /// Fails with `BgfxError::NotSupported` if `I` is `u32` and the renderer lacks
+    /// `CAPS_INDEX32`, with `BgfxError::EmptyBuffer` if `indices` is empty, and with
+    /// `BgfxError::OutOfBounds` if `indices` is too large for a single buffer.
+    pub fn from_slice(bgfx: &Bgfx,
+                      indices: &[I],
+                      flags: BufferFlags)
+                      -> Result<IndexBuffer<'m, I>, BgfxError> {
+        let flags = if I::INDEX32 {
+            bgfx.require(CAPS_INDEX32)?;
+            flags | BUFFER_INDEX32
+        } else {
+            flags - BUFFER_INDEX32
+        };
+
+        if indices.is_empty() {
+            return Err(BgfxError::EmptyBuffer);
+        }
+
+        if mem::size_of_val(indices) > u32::MAX as usize {
+            return Err(BgfxError::OutOfBounds);
+        }
+
         unsafe {
+            let indices = Memory::copy(bgfx, indices);
+            let num = indices.size() / mem::size_of::<I>() as u32;
             let handle = bgfx_sys::bgfx_create_index_buffer(indices.handle, flags.bits());
-            IndexBuffer { handle: handle, _phantom: PhantomData }
+            Ok(IndexBuffer { handle: handle, num: num, _phantom: PhantomData })
         }
     }
 
 }
 
-impl<'m> Drop for IndexBuffer<'m> {
+impl<'m, I> IndexBuffer<'m, I> {
+
+    /// Number of indices the buffer holds.
+    #[inline]
+    pub fn len(&self) -> u32 {
+        self.num
+    }
+
+    /// Returns `true` if the buffer holds no indices.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.num == 0
+    }
+
+}
+
+impl<'m, I> Drop for IndexBuffer<'m, I> {
 
     #[inline]
     fn drop(&mut self) {
@@ -529,9 +965,24 @@ impl<'m> Drop for IndexBuffer<'m> {
 }
 
 /// Vertex data buffer.
-pub struct VertexBuffer<'m> {
+///
+/// Buffers created from a slice of [`Vertex`] values with [`from_slice`] carry their vertex type
+/// in `T`. Buffers created from untyped memory with [`new`] leave it as `()`.
+///
+/// `T` records what the buffer was created from, and is what [`len`] counts. The layout itself is
+/// fixed by the vertex declaration the buffer is created with, so [`Bgfx::set_vertex_buffer`]
+/// accepts buffers of any vertex type, and no check happens when binding. Whether the layout
+/// matches the inputs of a shader is not checked either.
+///
+/// [`Bgfx::set_vertex_buffer`]: struct.Bgfx.html#method.set_vertex_buffer
+/// [`Vertex`]: trait.Vertex.html
+/// [`from_slice`]: #method.from_slice
+/// [`len`]: #method.len
+/// [`new`]: #method.new
+pub struct VertexBuffer<'m, T = ()> {
     handle: bgfx_sys::bgfx_vertex_buffer_handle_t,
-    _phantom: PhantomData<&'m Bgfx>,
+    num: u32,
+    _phantom: PhantomData<(&'m Bgfx, T)>,
 }
 
 impl<'m> VertexBuffer<'m> {
@@ -542,95 +993,476 @@ impl<'m> VertexBuffer<'m> {
                    decl: &'v VertexDecl,
                    flags: BufferFlags)
                    -> VertexBuffer<'m> {
+        let num = verts.size().checked_div(decl.decl.stride as u32).unwrap_or(0);
+
         unsafe {
             let handle = bgfx_sys::bgfx_create_vertex_buffer(verts.handle,
                                                              &decl.decl,
                                                              flags.bits());
-            VertexBuffer { handle: handle, _phantom: PhantomData }
+            VertexBuffer { handle: handle, num: num, _phantom: PhantomData }
         }
     }
 
 }
 
-impl<'m> Drop for VertexBuffer<'m> {
+impl<'m, T: Vertex> VertexBuffer<'m, T> {
 
-    #[inline]
-    fn drop(&mut self) {
-        unsafe { bgfx_sys::bgfx_destroy_vertex_buffer(self.handle) }
+    /// Creates a new vertex buffer holding a copy of `verts`, laid out according to `T::decl()`.
+    ///
+    /// Fails with `BgfxError::StrideMismatch` if the stride of `T::decl()` doesn't match the size
+    /// of `T`, with `BgfxError::EmptyBuffer` if `verts` is empty, and with
+    /// `BgfxError::OutOfBounds` if `verts` is too large for a single buffer.
+    pub fn from_slice(bgfx: &Bgfx,
+                      verts: &[T],
+                      flags: BufferFlags)
+                      -> Result<VertexBuffer<'m, T>, BgfxError> {
+        let decl = T::decl();
+        let stride = decl.decl.stride as usize;
+        let size = mem::size_of::<T>();
+
+        if stride != size {
+            return Err(BgfxError::StrideMismatch { expected: stride, found: size });
+        }
+
+        if verts.is_empty() {
+            return Err(BgfxError::EmptyBuffer);
+        }
+
+        if mem::size_of_val(verts) > u32::MAX as usize {
+            return Err(BgfxError::OutOfBounds);
+        }
+
+        unsafe {
+            let verts = Memory::copy(bgfx, verts);
+            let num = verts.size() / size as u32;
+            let handle = bgfx_sys::bgfx_create_vertex_buffer(verts.handle,
+                                                             &decl.decl,
+                                                             flags.bits());
+            Ok(VertexBuffer { handle: handle, num: num, _phantom: PhantomData })
+        }
     }
 
 }
 
-pub struct InstanceDataBuffer<T: 'static>
-{
-    pub data: &'static mut [T],
-    pidb: *const bgfx_sys::bgfx_instance_data_buffer_t
+impl<'m, T> VertexBuffer<'m, T> {
+
+    /// Number of vertices the buffer holds.
+    #[inline]
+    pub fn len(&self) -> u32 {
+        self.num
+    }
+
+    /// Returns `true` if the buffer holds no vertices.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.num == 0
+    }
+
 }
 
-impl<T> Drop for InstanceDataBuffer<T> {
+impl<'m, T> Drop for VertexBuffer<'m, T> {
 
     #[inline]
     fn drop(&mut self) {
-        panic!("Allocated instance data buffer MUST be given back to bgfx or leaks will happen");
+        unsafe { bgfx_sys::bgfx_destroy_vertex_buffer(self.handle) }
     }
 
 }
 
-pub struct TransientIndexBuffer {
-    pub data: &'static mut [u32],
-    btib: bgfx_sys::bgfx_transient_index_buffer_t,
+/// Dynamic vertex index buffer.
+///
+/// Unlike [`IndexBuffer`], the contents of a dynamic index buffer can be updated after creation.
+///
+/// [`IndexBuffer`]: struct.IndexBuffer.html
+pub struct DynamicIndexBuffer<'m> {
+    handle: bgfx_sys::bgfx_dynamic_index_buffer_handle_t,
+    num: u32,
+    flags: BufferFlags,
+    _phantom: PhantomData<&'m ()>,
 }
 
-pub struct TransientVertexBuffer<T: 'static> {
-    pub data: &'static mut [T],
-    btvb: bgfx_sys::bgfx_transient_vertex_buffer_t,
-}
+impl<'m> DynamicIndexBuffer<'m> {
 
-/// Describes the structure of a vertex.
-pub struct VertexDecl {
-    decl: bgfx_sys::bgfx_vertex_decl_t,
-}
+    /// Creates a new, uninitialized dynamic index buffer with room for `num` indices.
+    #[inline]
+    pub fn new(num: u32, flags: BufferFlags) -> DynamicIndexBuffer<'m> {
+        unsafe {
+            let handle = bgfx_sys::bgfx_create_dynamic_index_buffer(num, flags.bits());
+            DynamicIndexBuffer { handle: handle, num: num, flags: flags, _phantom: PhantomData }
+        }
+    }
 
-impl VertexDecl {
+    /// Creates a new dynamic index buffer from bgfx-managed memory.
+    #[inline]
+    pub fn from_memory(indices: Memory<'m>, flags: BufferFlags) -> DynamicIndexBuffer<'m> {
+        let num = indices.size() / DynamicIndexBuffer::index_size(flags);
 
-    /// Creates a new vertex declaration using a [`VertexDeclBuilder`].
-    ///
-    /// # Example
+        unsafe {
+            let handle = bgfx_sys::bgfx_create_dynamic_index_buffer_mem(indices.handle,
+                                                                        flags.bits());
+            DynamicIndexBuffer { handle: handle, num: num, flags: flags, _phantom: PhantomData }
+        }
+    }
+
+    /// Number of indices the buffer holds.
+    #[inline]
+    pub fn len(&self) -> u32 {
+        self.num
+    }
+
+    /// Returns `true` if the buffer holds no indices.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.num == 0
+    }
+
+    /// Updates the buffer with new indices, starting at index `start`.
     ///
-    /// ```
-    /// let decl = bgfx::VertexDecl::new(None)
-    ///                .add(bgfx::Attrib::Position, 3, bgfx::AttribType::Float)
-    ///                .add(bgfx::Attrib::Color0, 4, bgfx::AttribType::Uint8(true))
-    ///                .end();
-    /// ```
+    /// Fails with `BgfxError::OutOfBounds` if the size of `indices` isn't a multiple of the index
+    /// size, or if the update doesn't fit within the buffer, unless the buffer was created with
+    /// `BUFFER_ALLOW_RESIZE`, in which case it is grown to fit.
     ///
-    /// [`VertexDeclBuilder`]: struct.VertexDeclBuilder.html
-    #[inline]
-    pub fn new(renderer: Option<RendererType>) -> VertexDeclBuilder {
-        let renderer = renderer.unwrap_or(RendererType::Null) as bgfx_sys::bgfx_renderer_type_t;
+    /// **IMPORTANT:** If the update fails, `indices` is never passed to bgfx, and will leak.
+    pub fn update(&mut self, start: u32, indices: Memory<'m>) -> Result<(), BgfxError> {
+        let size = DynamicIndexBuffer::index_size(self.flags);
+        if indices.size() % size != 0 {
+            return Err(BgfxError::OutOfBounds);
+        }
 
-        unsafe {
-            let mut descr = VertexDeclBuilder { decl: mem::uninitialized() };
-            bgfx_sys::bgfx_vertex_decl_begin(&mut descr.decl, renderer);
-            descr
+        let num = indices.size() / size;
+        let end = start as u64 + num as u64;
+
+        if end > self.num as u64 {
+            if !self.flags.contains(BUFFER_ALLOW_RESIZE) {
+                return Err(BgfxError::OutOfBounds);
+            }
+            self.num = end as u32;
         }
+
+        unsafe { bgfx_sys::bgfx_update_dynamic_index_buffer(self.handle, start, indices.handle) }
+        Ok(())
+    }
+
+    #[inline]
+    fn index_size(flags: BufferFlags) -> u32 {
+        if flags.contains(BUFFER_INDEX32) { 4 } else { 2 }
     }
 
 }
 
-/// Builder for `VertexDecl` instances.
-pub struct VertexDeclBuilder {
-    decl: bgfx_sys::bgfx_vertex_decl_t,
+impl<'m> Drop for DynamicIndexBuffer<'m> {
+
+    #[inline]
+    fn drop(&mut self) {
+        unsafe { bgfx_sys::bgfx_destroy_dynamic_index_buffer(self.handle) }
+    }
+
 }
 
-impl VertexDeclBuilder {
+/// Dynamic vertex data buffer, holding vertices of type `T`.
+///
+/// Unlike [`VertexBuffer`], the contents of a dynamic vertex buffer can be updated after creation.
+///
+/// [`VertexBuffer`]: struct.VertexBuffer.html
+pub struct DynamicVertexBuffer<'m, T> {
+    handle: bgfx_sys::bgfx_dynamic_vertex_buffer_handle_t,
+    num: u32,
+    flags: BufferFlags,
+    _phantom: PhantomData<(&'m (), T)>,
+}
 
-    /// Adds a field to the structure descriptor. See [`VertexDecl::new`] for an example.
-    ///
-    /// [`VertexDecl::new`]: struct.VertexDecl.html#method.new
-    pub fn add(&mut self, attrib: Attrib, count: u8, kind: AttribType) -> &mut Self {
-        let mut normalized = false;
-        let mut as_int = false;
+impl<'m, T> DynamicVertexBuffer<'m, T> {
+
+    /// Creates a new, uninitialized dynamic vertex buffer with room for `num` vertices.
+    ///
+    /// Fails with `BgfxError::StrideMismatch` if the stride of `decl` doesn't match the size of
+    /// `T`, or if `T` is zero-sized.
+    #[inline]
+    pub fn new(num: u32,
+               decl: &VertexDecl,
+               flags: BufferFlags)
+               -> Result<DynamicVertexBuffer<'m, T>, BgfxError> {
+        DynamicVertexBuffer::<T>::check_stride(decl)?;
+
+        unsafe {
+            let handle = bgfx_sys::bgfx_create_dynamic_vertex_buffer(num, &decl.decl, flags.bits());
+            Ok(DynamicVertexBuffer {
+                handle: handle,
+                num: num,
+                flags: flags,
+                _phantom: PhantomData,
+            })
+        }
+    }
+
+    /// Creates a new dynamic vertex buffer from bgfx-managed memory.
+    ///
+    /// Fails with `BgfxError::StrideMismatch` if the stride of `decl` doesn't match the size of
+    /// `T`, or if `T` is zero-sized.
+    ///
+    /// **IMPORTANT:** If creation fails, `verts` is never passed to bgfx, and will leak.
+    #[inline]
+    pub fn from_memory(verts: Memory<'m>,
+                       decl: &VertexDecl,
+                       flags: BufferFlags)
+                       -> Result<DynamicVertexBuffer<'m, T>, BgfxError> {
+        DynamicVertexBuffer::<T>::check_stride(decl)?;
+        let num = verts.size() / decl.decl.stride as u32;
+
+        unsafe {
+            let handle = bgfx_sys::bgfx_create_dynamic_vertex_buffer_mem(verts.handle,
+                                                                         &decl.decl,
+                                                                         flags.bits());
+            Ok(DynamicVertexBuffer {
+                handle: handle,
+                num: num,
+                flags: flags,
+                _phantom: PhantomData,
+            })
+        }
+    }
+
+    /// Number of vertices the buffer holds.
+    #[inline]
+    pub fn len(&self) -> u32 {
+        self.num
+    }
+
+    /// Returns `true` if the buffer holds no vertices.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.num == 0
+    }
+
+    /// Updates the buffer with new vertices, starting at vertex `start`.
+    ///
+    /// Fails with `BgfxError::OutOfBounds` if the size of `verts` isn't a multiple of the size of
+    /// `T`, or if the update doesn't fit within the buffer, unless the buffer was created with
+    /// `BUFFER_ALLOW_RESIZE`, in which case it is grown to fit.
+    ///
+    /// **IMPORTANT:** If the update fails, `verts` is never passed to bgfx, and will leak.
+    pub fn update(&mut self, start: u32, verts: Memory<'m>) -> Result<(), BgfxError> {
+        let size = mem::size_of::<T>();
+        if verts.size() as usize % size != 0 {
+            return Err(BgfxError::OutOfBounds);
+        }
+
+        let num = verts.size() as usize / size;
+        let end = start as u64 + num as u64;
+
+        if end > self.num as u64 {
+            if !self.flags.contains(BUFFER_ALLOW_RESIZE) {
+                return Err(BgfxError::OutOfBounds);
+            }
+            self.num = end as u32;
+        }
+
+        unsafe { bgfx_sys::bgfx_update_dynamic_vertex_buffer(self.handle, start, verts.handle) }
+        Ok(())
+    }
+
+    #[inline]
+    fn check_stride(decl: &VertexDecl) -> Result<(), BgfxError> {
+        let stride = decl.decl.stride as usize;
+        let size = mem::size_of::<T>();
+
+        // Zero-sized vertices can't be counted, and bgfx can't draw them anyway.
+        if stride == size && size != 0 {
+            Ok(())
+        } else {
+            Err(BgfxError::StrideMismatch { expected: stride, found: size })
+        }
+    }
+
+}
+
+impl<'m, T> Drop for DynamicVertexBuffer<'m, T> {
+
+    #[inline]
+    fn drop(&mut self) {
+        unsafe { bgfx_sys::bgfx_destroy_dynamic_vertex_buffer(self.handle) }
+    }
+
+}
+
+/// Indirect draw buffer.
+///
+/// Holds draw or dispatch arguments, typically written by a compute program, for use with
+/// [`Bgfx::submit_indirect`] and [`Bgfx::dispatch_indirect`].
+///
+/// [`Bgfx::submit_indirect`]: struct.Bgfx.html#method.submit_indirect
+/// [`Bgfx::dispatch_indirect`]: struct.Bgfx.html#method.dispatch_indirect
+pub struct IndirectBuffer<'b> {
+    handle: bgfx_sys::bgfx_indirect_buffer_handle_t,
+    num: u32,
+    _phantom: PhantomData<&'b Bgfx>,
+}
+
+impl<'b> IndirectBuffer<'b> {
+
+    /// Creates a new indirect buffer with room for `num` draw calls.
+    ///
+    /// Fails with `BgfxError::NotSupported` if the renderer lacks draw indirect support.
+    #[inline]
+    pub fn new(bgfx: &'b Bgfx, num: u32) -> Result<IndirectBuffer<'b>, BgfxError> {
+        bgfx.require(CAPS_DRAW_INDIRECT)?;
+
+        unsafe {
+            let handle = bgfx_sys::bgfx_create_indirect_buffer(num);
+            Ok(IndirectBuffer { handle: handle, num: num, _phantom: PhantomData })
+        }
+    }
+
+    /// Number of draw calls the buffer has room for.
+    #[inline]
+    pub fn len(&self) -> u32 {
+        self.num
+    }
+
+    /// Returns `true` if the buffer has no room for draw calls.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.num == 0
+    }
+
+    /// Fails with `BgfxError::OutOfBounds` unless `num` entries starting at `start` fit within
+    /// the buffer.
+    #[inline]
+    fn check_range(&self, start: u16, num: u16) -> Result<(), BgfxError> {
+        if start as u32 + num as u32 <= self.num {
+            Ok(())
+        } else {
+            Err(BgfxError::OutOfBounds)
+        }
+    }
+
+}
+
+impl<'b> Drop for IndirectBuffer<'b> {
+
+    #[inline]
+    fn drop(&mut self) {
+        unsafe { bgfx_sys::bgfx_destroy_indirect_buffer(self.handle) }
+    }
+
+}
+
+/// Occlusion query.
+///
+/// Submitted through [`Bgfx::submit_occlusion_query`], and used for conditional rendering through
+/// [`Bgfx::set_condition`].
+///
+/// [`Bgfx::submit_occlusion_query`]: struct.Bgfx.html#method.submit_occlusion_query
+/// [`Bgfx::set_condition`]: struct.Bgfx.html#method.set_condition
+pub struct OcclusionQuery<'b> {
+    handle: bgfx_sys::bgfx_occlusion_query_handle_t,
+    _phantom: PhantomData<&'b Bgfx>,
+}
+
+impl<'b> OcclusionQuery<'b> {
+
+    /// Creates a new occlusion query.
+    ///
+    /// Fails with `BgfxError::NotSupported` if the renderer lacks occlusion query support.
+    #[inline]
+    pub fn new(bgfx: &'b Bgfx) -> Result<OcclusionQuery<'b>, BgfxError> {
+        bgfx.require(CAPS_OCCLUSION_QUERY)?;
+
+        unsafe {
+            let handle = bgfx_sys::bgfx_create_occlusion_query();
+            Ok(OcclusionQuery { handle: handle, _phantom: PhantomData })
+        }
+    }
+
+    /// Gets the result of the last submission of this query. Results bgfx doesn't document are
+    /// reported as `OcclusionQueryResult::NoResult`.
+    #[inline]
+    pub fn result(&self) -> OcclusionQueryResult {
+        let result = unsafe { bgfx_sys::bgfx_get_result(self.handle, ptr::null_mut()) };
+        OcclusionQueryResult::from_i32(result).unwrap_or(OcclusionQueryResult::NoResult)
+    }
+
+}
+
+impl<'b> Drop for OcclusionQuery<'b> {
+
+    #[inline]
+    fn drop(&mut self) {
+        unsafe { bgfx_sys::bgfx_destroy_occlusion_query(self.handle) }
+    }
+
+}
+
+pub struct InstanceDataBuffer<T: 'static>
+{
+    pub data: &'static mut [T],
+    pidb: *const bgfx_sys::bgfx_instance_data_buffer_t
+}
+
+impl<T> Drop for InstanceDataBuffer<T> {
+
+    #[inline]
+    fn drop(&mut self) {
+        panic!("Allocated instance data buffer MUST be given back to bgfx or leaks will happen");
+    }
+
+}
+
+pub struct TransientIndexBuffer {
+    pub data: &'static mut [u32],
+    btib: bgfx_sys::bgfx_transient_index_buffer_t,
+}
+
+pub struct TransientVertexBuffer<T: 'static> {
+    pub data: &'static mut [T],
+    btvb: bgfx_sys::bgfx_transient_vertex_buffer_t,
+}
+
+/// Describes the structure of a vertex.
+pub struct VertexDecl {
+    decl: bgfx_sys::bgfx_vertex_decl_t,
+}
+
+impl VertexDecl {
+
+    /// Creates a new vertex declaration using a [`VertexDeclBuilder`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let decl = bgfx::VertexDecl::new(None)
+    ///                .add(bgfx::Attrib::Position, 3, bgfx::AttribType::Float)
+    ///                .add(bgfx::Attrib::Color0, 4, bgfx::AttribType::Uint8(true))
+    ///                .end();
+    /// ```
+    ///
+    /// [`VertexDeclBuilder`]: struct.VertexDeclBuilder.html
+    #[inline]
+    pub fn new(renderer: Option<RendererType>) -> VertexDeclBuilder {
+        let renderer = renderer.unwrap_or(RendererType::Null) as bgfx_sys::bgfx_renderer_type_t;
+
+        unsafe {
+            let mut descr = VertexDeclBuilder { decl: mem::uninitialized() };
+            bgfx_sys::bgfx_vertex_decl_begin(&mut descr.decl, renderer);
+            descr
+        }
+    }
+
+}
+
+/// Builder for `VertexDecl` instances.
+pub struct VertexDeclBuilder {
+    decl: bgfx_sys::bgfx_vertex_decl_t,
+}
+
+impl VertexDeclBuilder {
+
+    /// Adds a field to the structure descriptor. See [`VertexDecl::new`] for an example.
+    ///
+    /// [`VertexDecl::new`]: struct.VertexDecl.html#method.new
+    pub fn add(&mut self, attrib: Attrib, count: u8, kind: AttribType) -> &mut Self {
+        let mut normalized = false;
+        let mut as_int = false;
 
         let kind = match kind {
             AttribType::Uint8(n) => {
@@ -688,67 +1520,1017 @@ impl VertexDeclBuilder {
         VertexDecl { decl: self.decl }
     }
 
+    /// Size of the attributes and gaps added so far, in bytes.
+    #[inline]
+    pub fn stride(&self) -> u16 {
+        self.decl.stride
+    }
+
     /// Indicates a gap in the vertex structure.
     #[inline]
     pub fn skip(&mut self, bytes: u8) -> &mut Self {
         unsafe {
-            bgfx_sys::bgfx_vertex_decl_skip(&mut self.decl, bytes);
+            bgfx_sys::bgfx_vertex_decl_skip(&mut self.decl, bytes);
+        }
+
+        self
+    }
+
+}
+
+
+/// Texture.
+
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone)]
+#[allow(non_snake_case)]
+pub struct TextureInfo { // = bgfx_sys::bgfx_texture_info_t
+    pub format: TextureFormat,
+    pub storageSize: u32,
+    pub width: u16,
+    pub height: u16,
+    pub depth: u16,
+    pub numLayers: u16,
+    pub numMips: u8,
+    pub bitsPerPixel: u8,
+    pub cubeMap: bool,
+}
+
+impl TextureInfo {
+
+    /// Pointer to pass to bgfx where it fills in a `bgfx_texture_info_t`.
+    #[inline]
+    fn as_raw(&mut self) -> *mut bgfx_sys::bgfx_texture_info_t {
+        self as *mut TextureInfo as *mut bgfx_sys::bgfx_texture_info_t
+    }
+
+}
+
+/// Region of a mip of a texture, given as its corner closest to the origin and its size.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TextureRegion {
+    /// Mip level.
+    pub mip: u8,
+
+    pub x: u16,
+    pub y: u16,
+
+    /// Depth slice of 3D textures. For blits of array and cube textures, the layer, or the layer
+    /// and side.
+    pub z: u16,
+
+    pub width: u16,
+    pub height: u16,
+
+    /// Number of depth slices, layers or sides, the same way as `z`.
+    pub depth: u16,
+}
+
+impl TextureRegion {
+
+    /// Creates a 2D region of a mip, with its top left corner at `(x, y)`.
+    #[inline]
+    pub fn new(mip: u8, (x, y): (u16, u16), (width, height): (u16, u16)) -> TextureRegion {
+        TextureRegion {
+            mip: mip,
+            x: x,
+            y: y,
+            z: 0,
+            width: width,
+            height: height,
+            depth: 1,
+        }
+    }
+
+}
+
+pub struct TextureHandle<'m> {
+    handle: bgfx_sys::bgfx_texture_handle_t,
+    pub info: TextureInfo,
+    flags: TextureFlags,
+    ratio: Option<BackbufferRatio>,
+    _phantom: PhantomData<&'m Bgfx>,
+}
+
+impl<'m> TextureHandle<'m> {
+
+    /// Creates a new texture from bgfx-managed memory.
+    #[inline]
+    pub fn new<'v>(buf: Memory<'m>,
+                   flags: TextureFlags,
+                   skip: u8)
+                   -> Self {
+        unsafe {
+            let mut info: TextureInfo = Default::default();
+            let handle = bgfx_sys::bgfx_create_texture(buf.handle,
+                                                       flags.bits(),
+                                                       skip,
+                                                       info.as_raw());
+            Self { handle: handle, info: info, flags: flags, ratio: None, _phantom: PhantomData }
+        }
+    }
+
+    /// Flags the texture was created with.
+    #[inline]
+    pub fn flags(&self) -> TextureFlags {
+        self.flags
+    }
+
+    /// Backbuffer ratio the texture is sized by, if it was created scaled. The width and height in
+    /// `info` are 0 for such textures.
+    #[inline]
+    pub fn ratio(&self) -> Option<BackbufferRatio> {
+        self.ratio
+    }
+
+    /// Updates a region of a layer and mip of a 2D texture. The region is given as its top left
+    /// corner and its size.
+    ///
+    /// `memory` holds the rows of the region, `pitch` bytes apart. If `pitch` is `None`, the rows
+    /// are tightly packed.
+    ///
+    /// Fails with `BgfxError::TextureTypeMismatch` unless this is a 2D texture, with
+    /// `BgfxError::OutOfBounds` if the layer, mip or region lie outside the texture, and with
+    /// `BgfxError::InvalidPitch` or `BgfxError::InvalidMemorySize` if `memory` doesn't match the
+    /// region. `memory` is leaked on failure.
+    pub fn update_2d(&self,
+                     layer: u16,
+                     mip: u8,
+                     (x, y): (u16, u16),
+                     (width, height): (u16, u16),
+                     memory: Memory,
+                     pitch: Option<u16>)
+                     -> Result<(), BgfxError> {
+        let info = self.current_info();
+        if info.cubeMap || info.depth > 1 {
+            return Err(BgfxError::TextureTypeMismatch);
+        }
+
+        self.check_region(&info, layer, mip, (x, y, 0), (width, height, 1))?;
+        let pitch = TextureHandle::check_memory(&info, width, height, 1, &memory, pitch)?;
+
+        unsafe {
+            bgfx_sys::bgfx_update_texture_2d(self.handle,
+                                             layer,
+                                             mip,
+                                             x,
+                                             y,
+                                             width,
+                                             height,
+                                             memory.handle,
+                                             pitch);
+        }
+
+        Ok(())
+    }
+
+    /// Updates a region of a mip of a 3D texture. The region is given as its corner closest to the
+    /// origin and its size. `memory` holds the tightly packed region.
+    ///
+    /// Fails with `BgfxError::TextureTypeMismatch` unless this is a 3D texture, with
+    /// `BgfxError::OutOfBounds` if the mip or region lie outside the texture, and with
+    /// `BgfxError::InvalidMemorySize` if `memory` doesn't match the region. `memory` is leaked on
+    /// failure.
+    pub fn update_3d(&self,
+                     mip: u8,
+                     (x, y, z): (u16, u16, u16),
+                     (width, height, depth): (u16, u16, u16),
+                     memory: Memory)
+                     -> Result<(), BgfxError> {
+        let info = self.current_info();
+        if info.cubeMap || info.numLayers > 1 {
+            return Err(BgfxError::TextureTypeMismatch);
+        }
+
+        self.check_region(&info, 0, mip, (x, y, z), (width, height, depth))?;
+        TextureHandle::check_memory(&info, width, height, depth, &memory, None)?;
+
+        unsafe {
+            bgfx_sys::bgfx_update_texture_3d(self.handle,
+                                             mip,
+                                             x,
+                                             y,
+                                             z,
+                                             width,
+                                             height,
+                                             depth,
+                                             memory.handle);
+        }
+
+        Ok(())
+    }
+
+    /// Updates a 2D region of a side and layer of a cube texture, such as one created with
+    /// [`TextureRegion::new`].
+    ///
+    /// `memory` holds the rows of the region, `pitch` bytes apart. If `pitch` is `None`, the rows
+    /// are tightly packed.
+    ///
+    /// Fails with `BgfxError::TextureTypeMismatch` unless this is a cube texture, with
+    /// `BgfxError::OutOfBounds` if the layer or region lie outside the texture, or the region
+    /// isn't 2D, and with `BgfxError::InvalidPitch` or `BgfxError::InvalidMemorySize` if `memory`
+    /// doesn't match the region. `memory` is leaked on failure.
+    ///
+    /// [`TextureRegion::new`]: struct.TextureRegion.html#method.new
+    pub fn update_cube(&self,
+                       layer: u16,
+                       side: CubeMapSide,
+                       region: TextureRegion,
+                       memory: Memory,
+                       pitch: Option<u16>)
+                       -> Result<(), BgfxError> {
+        let info = self.current_info();
+        if !info.cubeMap {
+            return Err(BgfxError::TextureTypeMismatch);
+        }
+
+        self.check_region(&info,
+                          layer,
+                          region.mip,
+                          (region.x, region.y, region.z),
+                          (region.width, region.height, region.depth))?;
+        let pitch =
+            TextureHandle::check_memory(&info, region.width, region.height, 1, &memory, pitch)?;
+
+        unsafe {
+            bgfx_sys::bgfx_update_texture_cube(self.handle,
+                                               layer,
+                                               side as u8,
+                                               region.mip,
+                                               region.x,
+                                               region.y,
+                                               region.width,
+                                               region.height,
+                                               memory.handle,
+                                               pitch);
+        }
+
+        Ok(())
+    }
+
+    /// Starts reading back the contents of a mip of the texture.
+    ///
+    /// The data becomes available once [`Bgfx::frame`] has returned the frame number bgfx
+    /// reported for the read. Poll the returned [`Readback`], or block on it with
+    /// [`Readback::wait`].
+    ///
+    /// Fails with `BgfxError::NotSupported` if the renderer can't read back textures, with
+    /// `BgfxError::MissingTextureFlags` unless the texture was created with `TEXTURE_READ_BACK`,
+    /// and with `BgfxError::OutOfBounds` if the mip doesn't exist.
+    ///
+    /// [`Bgfx::frame`]: struct.Bgfx.html#method.frame
+    /// [`Readback`]: struct.Readback.html
+    /// [`Readback::wait`]: struct.Readback.html#method.wait
+    pub fn read_async<'t>(&'t self,
+                          bgfx: &'t Bgfx,
+                          mip: u8)
+                          -> Result<Readback<'t>, BgfxError> {
+        bgfx.require(CAPS_TEXTURE_READ_BACK)?;
+
+        if !self.flags.contains(TEXTURE_READ_BACK) {
+            return Err(BgfxError::MissingTextureFlags(TEXTURE_READ_BACK));
+        }
+
+        let info = self.current_info();
+        if mip >= info.numMips {
+            return Err(BgfxError::OutOfBounds);
+        }
+
+        let (width, height, depth) = TextureHandle::mip_size(&info, mip);
+        let mut size_info: TextureInfo = Default::default();
+        unsafe {
+            bgfx_sys::bgfx_calc_texture_size(size_info.as_raw(),
+                                             width,
+                                             height,
+                                             depth,
+                                             false,
+                                             false,
+                                             1,
+                                             info.format as bgfx_sys::bgfx_texture_format_t);
+        }
+
+        let mut data = vec![0u8; size_info.storageSize as usize];
+        let frame = unsafe {
+            bgfx_sys::bgfx_read_texture(self.handle,
+                                        data.as_mut_ptr() as *mut ::std::os::raw::c_void,
+                                        mip)
+        };
+
+        Ok(Readback {
+            data: Some(data),
+            frame: frame,
+            bgfx: bgfx,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Returns the info of the texture, with the size of scaled textures resolved against the
+    /// current backbuffer size.
+    fn current_info(&self) -> TextureInfo {
+        let ratio = match self.ratio {
+            Some(ratio) => ratio,
+            None => return self.info,
+        };
+
+        let mut info = self.info;
+        unsafe {
+            let stats = &*bgfx_sys::bgfx_get_stats();
+            let (width, height) = ratio.scale(stats.width, stats.height);
+            bgfx_sys::bgfx_calc_texture_size(info.as_raw(),
+                                             width,
+                                             height,
+                                             1,
+                                             false,
+                                             self.info.numMips != 1,
+                                             cmp::max(self.info.numLayers, 1),
+                                             self.info.format as bgfx_sys::bgfx_texture_format_t);
+        }
+        info
+    }
+
+    /// Size of a mip level of the texture.
+    fn mip_size(info: &TextureInfo, mip: u8) -> (u16, u16, u16) {
+        (cmp::max(info.width >> mip, 1),
+         cmp::max(info.height >> mip, 1),
+         cmp::max(info.depth >> mip, 1))
+    }
+
+    /// Fails with `BgfxError::OutOfBounds` unless the region lies within the layer and mip.
+    fn check_region(&self,
+                    info: &TextureInfo,
+                    layer: u16,
+                    mip: u8,
+                    (x, y, z): (u16, u16, u16),
+                    (width, height, depth): (u16, u16, u16))
+                    -> Result<(), BgfxError> {
+        if layer >= cmp::max(info.numLayers, 1) || mip >= info.numMips {
+            return Err(BgfxError::OutOfBounds);
+        }
+
+        let (mip_width, mip_height, mip_depth) = TextureHandle::mip_size(info, mip);
+        let fits = |start: u16, len: u16, max: u16| start as u32 + len as u32 <= max as u32;
+
+        if fits(x, width, mip_width) && fits(y, height, mip_height) && fits(z, depth, mip_depth) {
+            Ok(())
+        } else {
+            Err(BgfxError::OutOfBounds)
+        }
+    }
+
+    /// Fails with `BgfxError::OutOfBounds` unless the blit region lies within the mip. For array
+    /// and cube textures, z addresses the layer, or the layer and side.
+    fn check_blit_region(info: &TextureInfo,
+                         mip: u8,
+                         (x, y, z): (u16, u16, u16),
+                         (width, height, depth): (u16, u16, u16))
+                         -> Result<(), BgfxError> {
+        if mip >= info.numMips {
+            return Err(BgfxError::OutOfBounds);
+        }
+
+        let (mip_width, mip_height, mip_depth) = TextureHandle::mip_size(info, mip);
+        let layers = cmp::max(info.numLayers, 1) as u32;
+        let max_z = if info.depth > 1 {
+            mip_depth as u32
+        } else if info.cubeMap {
+            6 * layers
+        } else {
+            layers
+        };
+
+        let fits = |start: u16, len: u16, max: u32| start as u32 + len as u32 <= max;
+
+        if fits(x, width, mip_width as u32) && fits(y, height, mip_height as u32) &&
+           fits(z, depth, max_z) {
+            Ok(())
+        } else {
+            Err(BgfxError::OutOfBounds)
+        }
+    }
+
+    /// Checks that `memory` holds a region of the given size with rows `pitch` bytes apart, and
+    /// returns the pitch to pass to bgfx.
+    fn check_memory(info: &TextureInfo,
+                    width: u16,
+                    height: u16,
+                    depth: u16,
+                    memory: &Memory,
+                    pitch: Option<u16>)
+                    -> Result<u16, BgfxError> {
+        let calc_size = |width: u16, height: u16, depth: u16| unsafe {
+            let mut region: TextureInfo = Default::default();
+            bgfx_sys::bgfx_calc_texture_size(region.as_raw(),
+                                             width,
+                                             height,
+                                             depth,
+                                             false,
+                                             false,
+                                             1,
+                                             info.format as bgfx_sys::bgfx_texture_format_t);
+            region.storageSize
+        };
+
+        let size = calc_size(width, height, depth);
+        let expected = match pitch {
+            None => size,
+            Some(pitch) => {
+                // A single row of pixels, or of blocks for block compressed formats.
+                let row = calc_size(width, 1, 1);
+                if (pitch as u32) < row {
+                    return Err(BgfxError::InvalidPitch { min: row, found: pitch });
+                }
+
+                let rows = size.checked_div(row).unwrap_or(0);
+                if rows == 0 { 0 } else { pitch as u32 * (rows - 1) + row }
+            }
+        };
+
+        let found = memory.size();
+        let valid = match pitch {
+            None => found == expected,
+            Some(_) => found >= expected,
+        };
+
+        if valid {
+            Ok(pitch.unwrap_or(u16::max_value()))
+        } else {
+            Err(BgfxError::InvalidMemorySize { expected: expected, found: found })
+        }
+    }
+
+}
+
+impl<'m> Drop for TextureHandle<'m> {
+
+    #[inline]
+    fn drop(&mut self) {
+        unsafe { bgfx_sys::bgfx_destroy_texture(self.handle) }
+    }
+
+}
+
+enum TextureKind {
+    Texture2d { width: u16, height: u16 },
+    Texture2dScaled(BackbufferRatio),
+    Texture3d { width: u16, height: u16, depth: u16 },
+    TextureCube { size: u16 },
+}
+
+/// Pending texture readback, as started by [`TextureHandle::read_async`].
+///
+/// Keeps the texture borrowed while the read is in flight. If it is dropped before
+/// [`Bgfx::frame`] has returned the frame the read completes at, its buffer is leaked, as the
+/// render thread may still write into it.
+///
+/// [`Bgfx::frame`]: struct.Bgfx.html#method.frame
+/// [`TextureHandle::read_async`]: struct.TextureHandle.html#method.read_async
+pub struct Readback<'t> {
+    data: Option<Vec<u8>>,
+    frame: u32,
+    bgfx: &'t Bgfx,
+    _phantom: PhantomData<&'t TextureHandle<'t>>,
+}
+
+impl<'t> Readback<'t> {
+
+    /// Frame number at which the data becomes available.
+    #[inline]
+    pub fn frame(&self) -> u32 {
+        self.frame
+    }
+
+    /// Whether the data is available, meaning [`Bgfx::frame`] has returned the frame the read
+    /// completes at.
+    ///
+    /// [`Bgfx::frame`]: struct.Bgfx.html#method.frame
+    #[inline]
+    pub fn is_ready(&self) -> bool {
+        self.bgfx.last_frame() >= self.frame
+    }
+
+    /// Gets the data, if it is available.
+    #[inline]
+    pub fn data(&self) -> Option<&[u8]> {
+        if self.is_ready() { self.data.as_ref().map(|data| &data[..]) } else { None }
+    }
+
+    /// Blocks until the data is available by calling [`Bgfx::frame`] as many times as needed, and
+    /// returns it.
+    ///
+    /// [`Bgfx::frame`]: struct.Bgfx.html#method.frame
+    pub fn wait(mut self) -> Vec<u8> {
+        while !self.is_ready() {
+            self.bgfx.frame();
+        }
+
+        self.data.take().unwrap()
+    }
+
+}
+
+impl<'t> Drop for Readback<'t> {
+
+    #[inline]
+    fn drop(&mut self) {
+        if !self.is_ready() {
+            if let Some(data) = self.data.take() {
+                mem::forget(data);
+            }
+        }
+    }
+
+}
+
+/// Texture builder.
+///
+/// Creates 2D, 3D and cube textures, optionally with mips, array layers and initial contents. The
+/// request is validated against the renderer's caps before it is handed to bgfx.
+///
+/// # Example
+///
+/// ```no_run
+/// # let bgfx: bgfx::Bgfx = unimplemented!();
+/// let texture = bgfx::TextureBuilder::texture_2d(256, 256)
+///     .format(bgfx::TextureFormat::RGBA8)
+///     .flags(bgfx::TEXTURE_RT)
+///     .build(&bgfx)
+///     .expect("Could not create render target");
+/// ```
+pub struct TextureBuilder<'m> {
+    kind: TextureKind,
+    has_mips: bool,
+    num_layers: u16,
+    format: TextureFormat,
+    flags: TextureFlags,
+    memory: Option<Memory<'m>>,
+}
+
+impl<'m> TextureBuilder<'m> {
+
+    /// Starts building a 2D texture of the given size.
+    #[inline]
+    pub fn texture_2d(width: u16, height: u16) -> TextureBuilder<'m> {
+        TextureBuilder::with_kind(TextureKind::Texture2d { width: width, height: height })
+    }
+
+    /// Starts building a 2D texture sized relative to the backbuffer. The texture is resized
+    /// automatically whenever the backbuffer is. Such textures can't have initial contents.
+    #[inline]
+    pub fn texture_2d_scaled(ratio: BackbufferRatio) -> TextureBuilder<'m> {
+        TextureBuilder::with_kind(TextureKind::Texture2dScaled(ratio))
+    }
+
+    /// Starts building a 3D texture of the given size. 3D textures have no array layers.
+    #[inline]
+    pub fn texture_3d(width: u16, height: u16, depth: u16) -> TextureBuilder<'m> {
+        TextureBuilder::with_kind(TextureKind::Texture3d {
+            width: width,
+            height: height,
+            depth: depth,
+        })
+    }
+
+    /// Starts building a cube texture with sides of the given size.
+    #[inline]
+    pub fn texture_cube(size: u16) -> TextureBuilder<'m> {
+        TextureBuilder::with_kind(TextureKind::TextureCube { size: size })
+    }
+
+    fn with_kind(kind: TextureKind) -> TextureBuilder<'m> {
+        TextureBuilder {
+            kind: kind,
+            has_mips: false,
+            num_layers: 1,
+            format: TextureFormat::RGBA8,
+            flags: TEXTURE_NONE,
+            memory: None,
+        }
+    }
+
+    /// Sets whether the texture has a full mip chain. Defaults to `false`.
+    #[inline]
+    pub fn mips(&mut self, has_mips: bool) -> &mut Self {
+        self.has_mips = has_mips;
+        self
+    }
+
+    /// Sets the number of array layers. Defaults to 1.
+    #[inline]
+    pub fn layers(&mut self, num_layers: u16) -> &mut Self {
+        self.num_layers = num_layers;
+        self
+    }
+
+    /// Sets the texture format. Defaults to `TextureFormat::RGBA8`.
+    #[inline]
+    pub fn format(&mut self, format: TextureFormat) -> &mut Self {
+        self.format = format;
+        self
+    }
+
+    /// Sets the texture flags. Defaults to `TEXTURE_NONE`.
+    #[inline]
+    pub fn flags(&mut self, flags: TextureFlags) -> &mut Self {
+        self.flags = flags;
+        self
+    }
+
+    /// Sets the initial contents of the texture. The memory must hold every layer and mip of the
+    /// texture.
+    #[inline]
+    pub fn memory(&mut self, memory: Memory<'m>) -> &mut Self {
+        self.memory = Some(memory);
+        self
+    }
+
+    /// Takes back the initial contents, such as after [`build`] failed, so they can still be
+    /// handed to bgfx.
+    ///
+    /// [`build`]: #method.build
+    #[inline]
+    pub fn take_memory(&mut self) -> Option<Memory<'m>> {
+        self.memory.take()
+    }
+
+    /// Creates the texture.
+    ///
+    /// Fails with `BgfxError::UnsupportedTextureFormat` if the format can't be used for this kind
+    /// of texture with the given flags, with `BgfxError::NotSupported` if the renderer lacks 3D or
+    /// array textures, with `BgfxError::TextureTooLarge` if a dimension exceeds the maximum
+    /// texture size, and with `BgfxError::InvalidMemorySize` if the initial contents have the
+    /// wrong size. On success the initial contents are handed to bgfx, and the builder can be
+    /// reused for textures without initial contents.
+    ///
+    /// **IMPORTANT:** On failure the initial contents stay in the builder, which can be changed and
+    /// built again. If they never reach bgfx, take them back with [`take_memory`], or they will
+    /// leak.
+    ///
+    /// [`take_memory`]: #method.take_memory
+    pub fn build(&mut self, bgfx: &Bgfx) -> Result<TextureHandle<'m>, BgfxError> {
+        let caps = bgfx.caps();
+        let info = self.validate(bgfx, &caps)?;
+
+        let raw_format = self.format as bgfx_sys::bgfx_texture_format_t;
+        let flags = self.flags.bits();
+        let memory = self.memory.take().map_or(ptr::null(), |m| m.handle);
+        let mut ratio = None;
+
+        let handle = unsafe {
+            match self.kind {
+                TextureKind::Texture2d { width, height } => {
+                    bgfx_sys::bgfx_create_texture_2d(width,
+                                                     height,
+                                                     self.has_mips,
+                                                     self.num_layers,
+                                                     raw_format,
+                                                     flags,
+                                                     memory)
+                }
+                TextureKind::Texture2dScaled(r) => {
+                    ratio = Some(r);
+                    bgfx_sys::bgfx_create_texture_2d_scaled(r as bgfx_sys::bgfx_backbuffer_ratio_t,
+                                                            self.has_mips,
+                                                            self.num_layers,
+                                                            raw_format,
+                                                            flags)
+                }
+                TextureKind::Texture3d { width, height, depth } => {
+                    bgfx_sys::bgfx_create_texture_3d(width,
+                                                     height,
+                                                     depth,
+                                                     self.has_mips,
+                                                     raw_format,
+                                                     flags,
+                                                     memory)
+                }
+                TextureKind::TextureCube { size } => {
+                    bgfx_sys::bgfx_create_texture_cube(size,
+                                                       self.has_mips,
+                                                       self.num_layers,
+                                                       raw_format,
+                                                       flags,
+                                                       memory)
+                }
+            }
+        };
+
+        Ok(TextureHandle {
+            handle: handle,
+            info: info,
+            flags: self.flags,
+            ratio: ratio,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Checks the request against the caps, and returns the info of the texture to create.
+    fn validate(&self, bgfx: &Bgfx, caps: &Caps) -> Result<TextureInfo, BgfxError> {
+        let (width, height, depth, cube_map) = match self.kind {
+            TextureKind::Texture2d { width, height } => (width, height, 1, false),
+            TextureKind::Texture2dScaled(_) => (0, 0, 1, false),
+            TextureKind::Texture3d { width, height, depth } => (width, height, depth, false),
+            TextureKind::TextureCube { size } => (size, size, 1, true),
+        };
+
+        let (format_caps, srgb_caps) = match self.kind {
+            TextureKind::Texture3d { .. } => {
+                (CAPS_FORMAT_TEXTURE_3D | CAPS_FORMAT_TEXTURE_3D_EMULATED,
+                 CAPS_FORMAT_TEXTURE_3D_SRGB)
+            }
+            TextureKind::TextureCube { .. } => {
+                (CAPS_FORMAT_TEXTURE_CUBE | CAPS_FORMAT_TEXTURE_CUBE_EMULATED,
+                 CAPS_FORMAT_TEXTURE_CUBE_SRGB)
+            }
+            _ => {
+                (CAPS_FORMAT_TEXTURE_2D | CAPS_FORMAT_TEXTURE_2D_EMULATED,
+                 CAPS_FORMAT_TEXTURE_2D_SRGB)
+            }
+        };
+
+        let supported = match self.format {
+            TextureFormat::Unknown | TextureFormat::UnknownDepth | TextureFormat::Count => {
+                CAPS_FORMAT_TEXTURE_NONE
+            }
+            format => caps.formats.get(format as usize).cloned().unwrap_or_default(),
+        };
+
+        if !supported.intersects(format_caps) ||
+           (self.flags.contains(TEXTURE_SRGB) && !supported.contains(srgb_caps)) ||
+           (self.flags.intersects(TEXTURE_RT_MASK) &&
+            !supported.contains(CAPS_FORMAT_TEXTURE_FRAMEBUFFER)) {
+            return Err(BgfxError::UnsupportedTextureFormat(self.format));
+        }
+
+        match self.kind {
+            TextureKind::Texture3d { .. } => bgfx.require(CAPS_TEXTURE_3D)?,
+            TextureKind::TextureCube { .. } if self.num_layers > 1 => {
+                bgfx.require(CAPS_TEXTURE_CUBE_ARRAY)?
+            }
+            _ if self.num_layers > 1 => bgfx.require(CAPS_TEXTURE_2D_ARRAY)?,
+            _ => {}
+        }
+
+        let max = caps.limits.maxTextureSize;
+        let size = *[width, height, depth].iter().max().unwrap() as u32;
+        if max > 0 && size > max {
+            return Err(BgfxError::TextureTooLarge { size: size, max: max });
+        }
+
+        let num_layers = match self.kind {
+            TextureKind::Texture3d { .. } => 1,
+            _ => cmp::max(self.num_layers, 1),
+        };
+
+        let mut info = TextureInfo {
+            format: self.format,
+            numLayers: num_layers,
+            cubeMap: cube_map,
+            ..Default::default()
+        };
+
+        if let TextureKind::Texture2dScaled(_) = self.kind {
+            if let Some(ref memory) = self.memory {
+                return Err(BgfxError::InvalidMemorySize { expected: 0, found: memory.size() });
+            }
+
+            // The size, and with it the number of mips, follows the backbuffer. A mip count of 0
+            // marks a full mip chain, see `TextureHandle::current_info`.
+            info.numMips = if self.has_mips { 0 } else { 1 };
+        } else {
+            unsafe {
+                bgfx_sys::bgfx_calc_texture_size(info.as_raw(),
+                                                 width,
+                                                 height,
+                                                 depth,
+                                                 cube_map,
+                                                 self.has_mips,
+                                                 num_layers,
+                                                 self.format as bgfx_sys::bgfx_texture_format_t);
+            }
+
+            if let Some(ref memory) = self.memory {
+                if memory.size() != info.storageSize {
+                    return Err(BgfxError::InvalidMemorySize {
+                        expected: info.storageSize,
+                        found: memory.size(),
+                    });
+                }
+            }
+        }
+
+        Ok(info)
+    }
+
+}
+
+/// Frame buffer attachment.
+pub struct Attachment<'m> {
+    /// Texture to render into.
+    pub texture: TextureHandle<'m>,
+
+    /// Mip level of the texture to render into.
+    pub mip: u16,
+
+    /// Cubemap side or depth layer/slice of the texture to render into.
+    pub layer: u16,
+}
+
+impl<'m> Attachment<'m> {
+
+    /// Creates an attachment rendering into the first mip and layer of the texture.
+    #[inline]
+    pub fn new(texture: TextureHandle<'m>) -> Attachment<'m> {
+        Attachment { texture: texture, mip: 0, layer: 0 }
+    }
+
+}
+
+/// Frame buffer.
+///
+/// The frame buffer keeps the textures it renders into alive until it is dropped itself.
+pub struct FrameBuffer<'m> {
+    handle: bgfx_sys::bgfx_frame_buffer_handle_t,
+    textures: Vec<TextureHandle<'m>>,
+    destroy_textures: bool,
+}
+
+impl<'m> FrameBuffer<'m> {
+
+    /// Creates a new frame buffer with a single texture of the given size and format.
+    #[inline]
+    pub fn new(width: u16,
+               height: u16,
+               format: TextureFormat,
+               flags: TextureFlags)
+               -> FrameBuffer<'m> {
+        let raw_format = format as bgfx_sys::bgfx_texture_format_t;
+
+        unsafe {
+            let handle =
+                bgfx_sys::bgfx_create_frame_buffer(width, height, raw_format, flags.bits());
+            let mut info: TextureInfo = Default::default();
+            bgfx_sys::bgfx_calc_texture_size(info.as_raw(),
+                                             width,
+                                             height,
+                                             1,
+                                             false,
+                                             false,
+                                             1,
+                                             raw_format);
+            FrameBuffer::from_owned_texture(handle, info, flags | TEXTURE_RT, None)
+        }
+    }
+
+    /// Creates a new frame buffer with a single texture of the given format, sized relative to
+    /// the backbuffer. The texture is resized automatically whenever the backbuffer is.
+    #[inline]
+    pub fn new_scaled(ratio: BackbufferRatio,
+                      format: TextureFormat,
+                      flags: TextureFlags)
+                      -> FrameBuffer<'m> {
+        let raw_ratio = ratio as bgfx_sys::bgfx_backbuffer_ratio_t;
+        let raw_format = format as bgfx_sys::bgfx_texture_format_t;
+
+        unsafe {
+            let handle =
+                bgfx_sys::bgfx_create_frame_buffer_scaled(raw_ratio, raw_format, flags.bits());
+            let info = TextureInfo {
+                format: format,
+                numLayers: 1,
+                numMips: 1,
+                ..Default::default()
+            };
+            FrameBuffer::from_owned_texture(handle, info, flags | TEXTURE_RT, Some(ratio))
+        }
+    }
+
+    /// Creates a new frame buffer rendering into the given textures. Ownerships of the textures
+    /// are moved to the frame buffer.
+    ///
+    /// Fails with `BgfxError::InvalidAttachmentCount` unless there are 1 to 8 textures, and with
+    /// `BgfxError::TextureSizeMismatch` if they differ in size.
+    pub fn from_textures(textures: Vec<TextureHandle<'m>>)
+                         -> Result<FrameBuffer<'m>, BgfxError> {
+        FrameBuffer::check_attachments(textures.iter().map(|t| (t, 0)))?;
+
+        let handles: Vec<_> = textures.iter().map(|t| t.handle).collect();
+
+        unsafe {
+            let handle = bgfx_sys::bgfx_create_frame_buffer_from_handles(handles.len() as u8,
+                                                                         handles.as_ptr(),
+                                                                         false);
+            Ok(FrameBuffer { handle: handle, textures: textures, destroy_textures: false })
         }
-
-        self
     }
 
-}
+    /// Creates a new frame buffer rendering into the given attachments. Ownerships of the
+    /// attached textures are moved to the frame buffer.
+    ///
+    /// Fails with `BgfxError::InvalidAttachmentCount` unless there are 1 to 8 attachments, with
+    /// `BgfxError::OutOfBounds` if an attachment names a mip its texture lacks, and with
+    /// `BgfxError::TextureSizeMismatch` if the attached mips differ in size.
+    pub fn from_attachments(attachments: Vec<Attachment<'m>>)
+                            -> Result<FrameBuffer<'m>, BgfxError> {
+        FrameBuffer::check_attachments(attachments.iter().map(|a| (&a.texture, a.mip)))?;
+
+        let raw: Vec<_> = attachments.iter()
+            .map(|a| {
+                bgfx_sys::bgfx_attachment_t {
+                    handle: a.texture.handle,
+                    mip: a.mip,
+                    layer: a.layer,
+                }
+            })
+            .collect();
 
+        unsafe {
+            let handle = bgfx_sys::bgfx_create_frame_buffer_from_attachment(raw.len() as u8,
+                                                                            raw.as_ptr(),
+                                                                            false);
+            let textures = attachments.into_iter().map(|a| a.texture).collect();
+            Ok(FrameBuffer { handle: handle, textures: textures, destroy_textures: false })
+        }
+    }
 
-/// Texture.
+    /// Checks that there are 1 to `MAX_FRAME_BUFFER_ATTACHMENTS` attachments, given as textures
+    /// and mips, and that all of them have the same size.
+    fn check_attachments<'a, I>(attachments: I) -> Result<(), BgfxError>
+        where I: ExactSizeIterator<Item = (&'a TextureHandle<'a>, u16)>
+    {
+        let count = attachments.len();
+        if count == 0 || count > MAX_FRAME_BUFFER_ATTACHMENTS {
+            return Err(BgfxError::InvalidAttachmentCount {
+                max: MAX_FRAME_BUFFER_ATTACHMENTS,
+                found: count,
+            });
+        }
 
-#[repr(C)]
-#[derive(Default, Debug)]
-#[allow(non_snake_case)]
-pub struct TextureInfo { // = bgfx_sys::bgfx_texture_info_t
-    pub format: TextureFormat,
-    pub storageSize: u32,
-    pub width: u16,
-    pub height: u16,
-    pub depth: u16,
-    pub numLayers: u16,
-    pub numMips: u8,
-    pub bitsPerPixel: u8,
-    pub cubeMap: bool,
-}
+        let mut expected = None;
+        for (texture, mip) in attachments {
+            let info = texture.current_info();
+            if mip >= info.numMips as u16 {
+                return Err(BgfxError::OutOfBounds);
+            }
 
-pub struct TextureHandle<'m> {
-    handle: bgfx_sys::bgfx_texture_handle_t,
-    pub info: TextureInfo,
-    _phantom: PhantomData<&'m Bgfx>,
-}
+            let (width, height, _) = TextureHandle::mip_size(&info, mip as u8);
+            match expected {
+                None => expected = Some((width, height)),
+                Some(expected) if expected != (width, height) => {
+                    return Err(BgfxError::TextureSizeMismatch {
+                        expected: expected,
+                        found: (width, height),
+                    });
+                }
+                Some(_) => {}
+            }
+        }
 
-impl<'m> TextureHandle<'m> {
+        Ok(())
+    }
 
-    /// Creates a new texture from bgfx-managed memory.
+    /// Gets the texture of the given attachment, if it exists.
     #[inline]
-    pub fn new<'v>(buf: Memory<'m>,
-                   flags: TextureFlags,
-                   skip: u8)
-                   -> Self {
-        unsafe {
-            let mut info: TextureInfo = Default::default();
-            let handle = bgfx_sys::bgfx_create_texture(buf.handle,
-                                                       flags.bits(),
-                                                       skip,
-                                                       std::mem::transmute(&mut info));
-            Self { handle: handle, info: info, _phantom: PhantomData }
-        }
+    pub fn texture(&self, attachment: usize) -> Option<&TextureHandle<'m>> {
+        self.textures.get(attachment)
+    }
+
+    /// Gets the textures of all attachments.
+    #[inline]
+    pub fn textures(&self) -> &[TextureHandle<'m>] {
+        &self.textures
+    }
+
+    /// Wraps a frame buffer whose single texture was created, and will be destroyed, by bgfx.
+    unsafe fn from_owned_texture(handle: bgfx_sys::bgfx_frame_buffer_handle_t,
+                                 info: TextureInfo,
+                                 flags: TextureFlags,
+                                 ratio: Option<BackbufferRatio>)
+                                 -> FrameBuffer<'m> {
+        let texture = TextureHandle {
+            handle: bgfx_sys::bgfx_get_texture(handle, 0),
+            info: info,
+            flags: flags,
+            ratio: ratio,
+            _phantom: PhantomData,
+        };
+
+        FrameBuffer { handle: handle, textures: vec![texture], destroy_textures: true }
     }
 
 }
 
-impl<'m> Drop for TextureHandle<'m> {
+impl<'m> Drop for FrameBuffer<'m> {
 
     #[inline]
     fn drop(&mut self) {
-        unsafe { bgfx_sys::bgfx_destroy_texture(self.handle) }
+        unsafe { bgfx_sys::bgfx_destroy_frame_buffer(self.handle) }
+
+        // Textures created by bgfx are destroyed along with the frame buffer.
+        if self.destroy_textures {
+            for texture in self.textures.drain(..) {
+                mem::forget(texture);
+            }
+        }
     }
 
 }
@@ -756,6 +2538,8 @@ impl<'m> Drop for TextureHandle<'m> {
 /// Uniform.
 pub struct UniformHandle<'m> {
     handle: bgfx_sys::bgfx_uniform_handle_t,
+    kind: UniformType,
+    num: u16,
     _phantom: PhantomData<&'m Bgfx>,
 }
 
@@ -772,10 +2556,52 @@ impl<'m> UniformHandle<'m> {
             let handle = bgfx_sys::bgfx_create_uniform(s.as_ptr(),
                                                        _type as bgfx_sys::bgfx_uniform_type_t,
                                                        num);
-            Self { handle: handle, _phantom: PhantomData }
+            Self { handle: handle, kind: _type, num: num, _phantom: PhantomData }
         }
     }
 
+    /// Type the uniform was created with.
+    #[inline]
+    pub fn kind(&self) -> UniformType {
+        self.kind
+    }
+
+    /// Number of array elements the uniform was created with.
+    #[inline]
+    pub fn num(&self) -> u16 {
+        self.num
+    }
+
+}
+
+/// Description of a uniform, as declared by a shader.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UniformInfo {
+    /// Name of the uniform.
+    pub name: String,
+
+    /// Type of the uniform.
+    pub kind: UniformType,
+
+    /// Number of array elements.
+    pub num: u16,
+}
+
+impl UniformInfo {
+
+    unsafe fn from_handle(handle: bgfx_sys::bgfx_uniform_handle_t) -> Option<UniformInfo> {
+        let mut info: bgfx_sys::bgfx_uniform_info_t = mem::zeroed();
+        bgfx_sys::bgfx_get_uniform_info(handle, &mut info);
+
+        UniformType::from_i32(info.type_).map(|kind| {
+            UniformInfo {
+                name: ffi::CStr::from_ptr(info.name.as_ptr()).to_string_lossy().into_owned(),
+                kind: kind,
+                num: info.num,
+            }
+        })
+    }
+
 }
 
 impl<'m> Drop for UniformHandle<'m> {
@@ -888,15 +2714,24 @@ impl fmt::Debug for Caps {
 ///
 /// [`bgfx::init`]: fn.init.html
 pub struct Bgfx {
-    // This dummy field only exists so this type can't be publicly instantiated.
-    _dummy: u32,
+    // These must outlive bgfx itself, which is guaranteed by `drop` shutting bgfx down before
+    // any fields are dropped.
+    callback: Box<CallbackShim>,
+    _allocator: Option<Box<AllocatorShim>>,
+
+    // Number of the last frame returned by `frame`, used to tell when readbacks have completed.
+    last_frame: AtomicU32,
 }
 
 impl Bgfx {
 
     #[inline]
-    fn new() -> Bgfx {
-        Bgfx { _dummy: 0 }
+    fn new(callback: Box<CallbackShim>, allocator: Option<Box<AllocatorShim>>) -> Bgfx {
+        Bgfx {
+            callback: callback,
+            _allocator: allocator,
+            last_frame: AtomicU32::new(0),
+        }
     }
 
     /// Gets the caps Bgfx is supporting
@@ -907,6 +2742,36 @@ impl Bgfx {
         caps
     }
 
+    /// Gets the statistics of the last frame.
+    #[inline]
+    pub fn stats(&self) -> Stats {
+        unsafe { Stats::from_raw(&*bgfx_sys::bgfx_get_stats()) }
+    }
+
+    /// Takes the errors reported since the last call, oldest first. These are the fatal errors
+    /// bgfx reports, as `BgfxError::Fatal`, and failed screen shot requests, as
+    /// `BgfxError::ScreenshotFailed` and `BgfxError::ScreenshotLost`.
+    ///
+    /// Errors are reported from both the API and the render thread, so an error caused by a call
+    /// may only show up after a later `frame`. After `Fatal::DeviceLost`, all resources should be
+    /// recreated.
+    ///
+    /// Only fatal errors bgfx keeps running after show up here, since the process is aborted after
+    /// the others, unless [`Callback::continue_after_fatal`] says otherwise. `Fatal::DebugCheck`
+    /// failures are not queued, as they may fire every frame.
+    ///
+    /// [`Callback::continue_after_fatal`]: callback/trait.Callback.html#method.continue_after_fatal
+    pub fn take_errors(&self) -> Vec<BgfxError> {
+        self.callback.take_errors()
+    }
+
+    /// Fails with `BgfxError::NotSupported` unless the renderer supports all of `caps`.
+    fn require(&self, caps: CapsFlags) -> Result<(), BgfxError> {
+        let missing = caps - self.caps().supported;
+
+        if missing.is_empty() { Ok(()) } else { Err(BgfxError::NotSupported(missing)) }
+    }
+
     /// Clears the debug text overlay.
     #[inline]
     pub fn dbg_text_clear(&self, attr: Option<u8>, small: Option<bool>) {
@@ -946,13 +2811,25 @@ impl Bgfx {
     /// Finish the frame, syncing up with the render thread. Returns an incrementing frame counter.
     #[inline]
     pub fn frame(&self) -> u32 {
-        unsafe { bgfx_sys::bgfx_frame(false) }
+        let frame = unsafe { bgfx_sys::bgfx_frame(false) };
+        self.last_frame.store(frame, Ordering::Release);
+        self.callback.expire_screenshots(frame);
+        frame
     }
 
     /// Finish the frame, syncing up with the render thread. Returns an incrementing frame counter.
     #[inline]
     pub fn frame_capture(&self) -> u32 {
-        unsafe { bgfx_sys::bgfx_frame(true) }
+        let frame = unsafe { bgfx_sys::bgfx_frame(true) };
+        self.last_frame.store(frame, Ordering::Release);
+        self.callback.expire_screenshots(frame);
+        frame
+    }
+
+    /// Number of the last frame returned by `frame` or `frame_capture`.
+    #[inline]
+    fn last_frame(&self) -> u32 {
+        self.last_frame.load(Ordering::Acquire)
     }
 
     /// Gets the type of the renderer in use.
@@ -1063,13 +2940,134 @@ impl Bgfx {
         unsafe { bgfx_sys::bgfx_set_transient_index_buffer(&tib.btib, first_index as u32, num_indices as u32); }
     }
 
-    /// Sets the index buffer to use for rendering.
+    /// Sets the index buffer to use for rendering. Buffers of any index type are accepted, see
+    /// [`IndexBuffer`].
+    ///
+    /// [`IndexBuffer`]: struct.IndexBuffer.html
     #[inline]
-    pub fn set_index_buffer(&self, ibh: &IndexBuffer) {
+    pub fn set_index_buffer<I>(&self, ibh: &IndexBuffer<I>) {
         // TODO: How to solve lifetimes...
         unsafe { bgfx_sys::bgfx_set_index_buffer(ibh.handle, 0, std::u32::MAX) }
     }
 
+    /// Sets the dynamic index buffer to use for rendering.
+    #[inline]
+    pub fn set_dynamic_index_buffer(&self, ibh: &DynamicIndexBuffer) {
+        unsafe { bgfx_sys::bgfx_set_dynamic_index_buffer(ibh.handle, 0, std::u32::MAX) }
+    }
+
+    /// Sets a range of the dynamic index buffer to use for rendering.
+    #[inline]
+    pub fn set_dynamic_index_buffer_partial(&self,
+                                            ibh: &DynamicIndexBuffer,
+                                            first_index: u32,
+                                            num_indices: u32) {
+        unsafe { bgfx_sys::bgfx_set_dynamic_index_buffer(ibh.handle, first_index, num_indices) }
+    }
+
+    /// Copies a region of `src` into `dst`, as part of the given view. Use
+    /// [`FrameBuffer::texture`] to blit to or from frame buffer attachments.
+    ///
+    /// `region` is copied from the region of the same size at mip `src_mip` of `src`, whose
+    /// corner closest to the origin is `src_at`. For array and cube textures, z addresses the
+    /// layer, or the layer and side.
+    ///
+    /// Fails with `BgfxError::NotSupported` if the renderer can't blit, with
+    /// `BgfxError::MissingTextureFlags` unless `dst` was created with `TEXTURE_BLIT_DST`, with
+    /// `BgfxError::TextureFormatMismatch` if the formats differ, and with
+    /// `BgfxError::OutOfBounds` if either region lies outside its texture.
+    ///
+    /// [`FrameBuffer::texture`]: struct.FrameBuffer.html#method.texture
+    pub fn blit(&self,
+                view: u8,
+                dst: &TextureHandle,
+                region: TextureRegion,
+                src: &TextureHandle,
+                src_mip: u8,
+                (src_x, src_y, src_z): (u16, u16, u16))
+                -> Result<(), BgfxError> {
+        self.require(CAPS_TEXTURE_BLIT)?;
+
+        if !dst.flags.contains(TEXTURE_BLIT_DST) {
+            return Err(BgfxError::MissingTextureFlags(TEXTURE_BLIT_DST));
+        }
+
+        let dst_info = dst.current_info();
+        let src_info = src.current_info();
+        if dst_info.format != src_info.format {
+            return Err(BgfxError::TextureFormatMismatch {
+                expected: dst_info.format,
+                found: src_info.format,
+            });
+        }
+
+        let size = (region.width, region.height, region.depth);
+        let dst_at = (region.x, region.y, region.z);
+        TextureHandle::check_blit_region(&dst_info, region.mip, dst_at, size)?;
+        TextureHandle::check_blit_region(&src_info, src_mip, (src_x, src_y, src_z), size)?;
+
+        unsafe {
+            bgfx_sys::bgfx_blit(view,
+                                dst.handle,
+                                region.mip,
+                                region.x,
+                                region.y,
+                                region.z,
+                                src.handle,
+                                src_mip,
+                                src_x,
+                                src_y,
+                                src_z,
+                                region.width,
+                                region.height,
+                                region.depth);
+        }
+
+        Ok(())
+    }
+
+    /// Requests a screen shot of the given frame buffer, or of the backbuffer if `frame_buffer` is
+    /// `None`. It is delivered to `target` on the render thread a few frames later.
+    ///
+    /// `target` can be a path to save a PNG file to, or a [`ScreenshotTarget`] wrapping a closure.
+    ///
+    /// Requests bgfx hasn't answered within 8 frames, such as requests for a frame buffer that was
+    /// destroyed in the meantime, are dropped without calling the closure, and reported as
+    /// `BgfxError::ScreenshotLost` by [`take_errors`]. Errors saving a PNG file are reported as
+    /// `BgfxError::ScreenshotFailed`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # let bgfx: bgfx::Bgfx = unimplemented!();
+    /// bgfx.request_screenshot(None, "screenshot.png");
+    /// bgfx.request_screenshot(None, bgfx::ScreenshotTarget::closure(|shot| {
+    ///     println!("Got a {}x{} screen shot", shot.width, shot.height);
+    /// }));
+    /// ```
+    ///
+    /// [`ScreenshotTarget`]: screenshot/enum.ScreenshotTarget.html
+    /// [`take_errors`]: #method.take_errors
+    pub fn request_screenshot<T>(&self, frame_buffer: Option<&FrameBuffer>, target: T)
+        where T: Into<ScreenshotTarget>
+    {
+        let handle = match frame_buffer {
+            Some(fb) => fb.handle,
+            None => bgfx_sys::bgfx_frame_buffer_handle_t { idx: std::u16::MAX },
+        };
+
+        let key = self.callback.add_screenshot(target.into(), self.last_frame());
+        let key = ffi::CString::new(key).unwrap();
+        unsafe { bgfx_sys::bgfx_request_screen_shot(handle, key.as_ptr()) }
+    }
+
+    /// Sets the condition for conditional rendering. The following draw call is only rendered if
+    /// the result of `query` matches `visible`.
+    #[inline]
+    pub fn set_condition(&self, query: &OcclusionQuery, visible: bool) {
+        unsafe { bgfx_sys::bgfx_set_condition(query.handle, visible) }
+    }
+
     /// Sets the render state.
     #[inline]
     pub fn set_state(&self, state: StateFlags, rgba: Option<u32>) {
@@ -1085,13 +3083,33 @@ impl Bgfx {
         }
     }
 
-    /// Sets the vertex buffer to use for rendering.
+    /// Sets the vertex buffer to use for rendering. Buffers of any vertex type are accepted, see
+    /// [`VertexBuffer`].
+    ///
+    /// [`VertexBuffer`]: struct.VertexBuffer.html
     #[inline]
-    pub fn set_vertex_buffer(&self, vbh: &VertexBuffer) {
+    pub fn set_vertex_buffer<T>(&self, vbh: &VertexBuffer<T>) {
         // TODO: How to solve lifetimes...
         unsafe { bgfx_sys::bgfx_set_vertex_buffer(vbh.handle, 0, std::u32::MAX) }
     }
 
+    /// Sets the dynamic vertex buffer to use for rendering.
+    #[inline]
+    pub fn set_dynamic_vertex_buffer<T>(&self, vbh: &DynamicVertexBuffer<T>) {
+        unsafe { bgfx_sys::bgfx_set_dynamic_vertex_buffer(vbh.handle, 0, std::u32::MAX) }
+    }
+
+    /// Sets a range of the dynamic vertex buffer to use for rendering.
+    #[inline]
+    pub fn set_dynamic_vertex_buffer_partial<T>(&self,
+                                                vbh: &DynamicVertexBuffer<T>,
+                                                start_vertex: u32,
+                                                num_vertices: u32) {
+        unsafe {
+            bgfx_sys::bgfx_set_dynamic_vertex_buffer(vbh.handle, start_vertex, num_vertices)
+        }
+    }
+
     /// Sets and consumes the allocated instance data buffer
     pub fn set_instance_data_buffer<T>(&self, idb: InstanceDataBuffer<T>) {
         unsafe {
@@ -1107,18 +3125,188 @@ impl Bgfx {
         unsafe { bgfx_sys::bgfx_set_texture(stage, uh.handle, th.handle, u32::max_value()) }
     }
 
+    /// Sets the value of a `UniformType::Vec4` uniform. See [`set_uniform_int1`] for the rules on
+    /// the number of values.
+    ///
+    /// [`set_uniform_int1`]: #method.set_uniform_int1
+    #[inline]
+    pub fn set_uniform_vec4(&self,
+                            uh: &UniformHandle,
+                            values: &[[f32; 4]])
+                            -> Result<(), BgfxError> {
+        self.set_uniform(uh, UniformType::Vec4, values)
+    }
+
+    /// Sets the value of a `UniformType::Mat3` uniform. See [`set_uniform_int1`] for the rules on
+    /// the number of values.
+    ///
+    /// [`set_uniform_int1`]: #method.set_uniform_int1
+    #[inline]
+    pub fn set_uniform_mat3(&self,
+                            uh: &UniformHandle,
+                            values: &[[f32; 9]])
+                            -> Result<(), BgfxError> {
+        self.set_uniform(uh, UniformType::Mat3, values)
+    }
+
+    /// Sets the value of a `UniformType::Mat4` uniform. See [`set_uniform_int1`] for the rules on
+    /// the number of values.
+    ///
+    /// [`set_uniform_int1`]: #method.set_uniform_int1
+    #[inline]
+    pub fn set_uniform_mat4(&self,
+                            uh: &UniformHandle,
+                            values: &[[f32; 16]])
+                            -> Result<(), BgfxError> {
+        self.set_uniform(uh, UniformType::Mat4, values)
+    }
+
+    /// Sets the value of a `UniformType::Int1` uniform, such as a sampler.
+    ///
+    /// Fails with `BgfxError::UniformTypeMismatch` if the uniform was created with another type,
+    /// and with `BgfxError::UniformCountMismatch` if `values` is empty or has more elements than
+    /// the uniform was created with. Passing fewer elements updates the start of the array.
+    #[inline]
+    pub fn set_uniform_int1(&self, uh: &UniformHandle, values: &[i32]) -> Result<(), BgfxError> {
+        self.set_uniform(uh, UniformType::Int1, values)
+    }
+
+    fn set_uniform<T>(&self,
+                      uh: &UniformHandle,
+                      kind: UniformType,
+                      values: &[T])
+                      -> Result<(), BgfxError> {
+        if uh.kind != kind {
+            return Err(BgfxError::UniformTypeMismatch { expected: uh.kind, found: kind });
+        }
+
+        if values.is_empty() || values.len() > uh.num as usize {
+            return Err(BgfxError::UniformCountMismatch { expected: uh.num, found: values.len() });
+        }
+
+        unsafe {
+            bgfx_sys::bgfx_set_uniform(uh.handle,
+                                       values.as_ptr() as *const ::std::os::raw::c_void,
+                                       values.len() as u16);
+        }
+
+        Ok(())
+    }
+
     /// Sets the options to use when clearing the given view.
     #[inline]
     pub fn set_view_clear(&self, id: u8, flags: ClearFlags, rgba: u32, depth: f32, stencil: u8) {
         unsafe { bgfx_sys::bgfx_set_view_clear(id, flags.bits(), rgba, depth, stencil) }
     }
 
+    /// Sets the options to use when clearing the given view, using colors from the palette for each
+    /// of up to 8 frame buffer attachments. See [`set_palette_color`].
+    ///
+    /// [`set_palette_color`]: #method.set_palette_color
+    #[inline]
+    pub fn set_view_clear_mrt(&self,
+                              id: u8,
+                              flags: ClearFlags,
+                              depth: f32,
+                              stencil: u8,
+                              colors: [u8; 8]) {
+        unsafe {
+            bgfx_sys::bgfx_set_view_clear_mrt(id,
+                                              flags.bits(),
+                                              depth,
+                                              stencil,
+                                              colors[0],
+                                              colors[1],
+                                              colors[2],
+                                              colors[3],
+                                              colors[4],
+                                              colors[5],
+                                              colors[6],
+                                              colors[7])
+        }
+    }
+
+    /// Sets a palette color, for use with [`set_view_clear_mrt`].
+    ///
+    /// [`set_view_clear_mrt`]: #method.set_view_clear_mrt
+    #[inline]
+    pub fn set_palette_color(&self, index: u8, rgba: [f32; 4]) {
+        unsafe { bgfx_sys::bgfx_set_palette_color(index, rgba.as_ptr()) }
+    }
+
+    /// Sets the frame buffer the given view renders into. Passing `None` makes the view render into
+    /// the backbuffer.
+    #[inline]
+    pub fn set_view_frame_buffer(&self, id: u8, frame_buffer: Option<&FrameBuffer>) {
+        let handle = match frame_buffer {
+            Some(fb) => fb.handle,
+            None => bgfx_sys::bgfx_frame_buffer_handle_t { idx: std::u16::MAX },
+        };
+
+        unsafe { bgfx_sys::bgfx_set_view_frame_buffer(id, handle) }
+    }
+
+    /// Sets the name of the given view, as shown in graphics debuggers and profilers.
+    #[inline]
+    pub fn set_view_name(&self, id: u8, name: &str) {
+        unsafe {
+            let name = ffi::CString::new(name).unwrap();
+            bgfx_sys::bgfx_set_view_name(id, name.as_ptr());
+        }
+    }
+
     /// Sets the rectangle to display the given view in.
     #[inline]
     pub fn set_view_rect(&self, id: u8, x: u16, y: u16, width: u16, height: u16) {
         unsafe { bgfx_sys::bgfx_set_view_rect(id, x, y, width, height) }
     }
 
+    /// Sets the rectangle to display the given view in, sized relative to the backbuffer. The
+    /// size follows the backbuffer when it is resized.
+    #[inline]
+    pub fn set_view_rect_auto(&self, id: u8, x: u16, y: u16, ratio: BackbufferRatio) {
+        let ratio = ratio as bgfx_sys::bgfx_backbuffer_ratio_t;
+        unsafe { bgfx_sys::bgfx_set_view_rect_auto(id, x, y, ratio) }
+    }
+
+    /// Sets the scissor rectangle of the given view. A rectangle of all zeroes disables the
+    /// scissor test.
+    #[inline]
+    pub fn set_view_scissor(&self, id: u8, x: u16, y: u16, width: u16, height: u16) {
+        unsafe { bgfx_sys::bgfx_set_view_scissor(id, x, y, width, height) }
+    }
+
+    /// Enables or disables sequential mode for the given view. In sequential mode, draw calls are
+    /// submitted in the order they were issued, instead of being sorted.
+    #[inline]
+    pub fn set_view_seq(&self, id: u8, enabled: bool) {
+        unsafe { bgfx_sys::bgfx_set_view_seq(id, enabled) }
+    }
+
+    /// Remaps the order in which views are rendered, starting at view `id`. Entry `i` of `order`
+    /// gives the view rendered in position `id + i`. An empty `order` restores the default order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `order` has more than 255 entries.
+    #[inline]
+    pub fn set_view_order(&self, id: u8, order: &[u8]) {
+        assert!(order.len() <= u8::max_value() as usize, "Too many views in view order");
+
+        let ptr = if order.is_empty() { ptr::null() } else { order.as_ptr() };
+        unsafe {
+            bgfx_sys::bgfx_set_view_order(id,
+                                          order.len() as u8,
+                                          ptr as *const ::std::os::raw::c_void)
+        }
+    }
+
+    /// Resets all settings of the given view to their defaults.
+    #[inline]
+    pub fn reset_view(&self, id: u8) {
+        unsafe { bgfx_sys::bgfx_reset_view(id) }
+    }
+
     /// Sets the view and projection matrices for the given view.
     #[inline]
     pub fn set_view_transform(&self, id: u8, view: &[f32; 16], proj: &[f32; 16]) {
@@ -1135,6 +3323,174 @@ impl Bgfx {
         unsafe { bgfx_sys::bgfx_submit(view, program.handle, 0, false) }
     }
 
+    /// Sets a texture mip as a compute image.
+    ///
+    /// If `format` is `None`, the format of the texture is used.
+    #[inline]
+    pub fn set_image(&self,
+                     stage: u8,
+                     uh: &UniformHandle,
+                     th: &TextureHandle,
+                     mip: u8,
+                     access: Access,
+                     format: Option<TextureFormat>) {
+        let format = format.unwrap_or(TextureFormat::Count);
+
+        unsafe {
+            bgfx_sys::bgfx_set_image(stage,
+                                     uh.handle,
+                                     th.handle,
+                                     mip,
+                                     access as bgfx_sys::bgfx_access_t,
+                                     format as bgfx_sys::bgfx_texture_format_t)
+        }
+    }
+
+    /// Submit a primitive for rendering, with an occlusion query. Returns the number of draw calls
+    /// used.
+    #[inline]
+    pub fn submit_occlusion_query(&self,
+                                  view: u8,
+                                  program: &Program,
+                                  query: &OcclusionQuery)
+                                  -> u32 {
+        unsafe {
+            bgfx_sys::bgfx_submit_occlusion_query(view, program.handle, query.handle, 0, false)
+        }
+    }
+
+    /// Sets an index buffer as a compute buffer.
+    #[inline]
+    pub fn set_compute_index_buffer<I>(&self,
+                                       stage: u8,
+                                       ibh: &IndexBuffer<I>,
+                                       access: Access) {
+        unsafe {
+            bgfx_sys::bgfx_set_compute_index_buffer(stage,
+                                                    ibh.handle,
+                                                    access as bgfx_sys::bgfx_access_t)
+        }
+    }
+
+    /// Sets a vertex buffer as a compute buffer.
+    #[inline]
+    pub fn set_compute_vertex_buffer<T>(&self,
+                                        stage: u8,
+                                        vbh: &VertexBuffer<T>,
+                                        access: Access) {
+        unsafe {
+            bgfx_sys::bgfx_set_compute_vertex_buffer(stage,
+                                                     vbh.handle,
+                                                     access as bgfx_sys::bgfx_access_t)
+        }
+    }
+
+    /// Sets a dynamic index buffer as a compute buffer.
+    #[inline]
+    pub fn set_compute_dynamic_index_buffer(&self,
+                                            stage: u8,
+                                            ibh: &DynamicIndexBuffer,
+                                            access: Access) {
+        unsafe {
+            bgfx_sys::bgfx_set_compute_dynamic_index_buffer(stage,
+                                                            ibh.handle,
+                                                            access as bgfx_sys::bgfx_access_t)
+        }
+    }
+
+    /// Sets a dynamic vertex buffer as a compute buffer.
+    #[inline]
+    pub fn set_compute_dynamic_vertex_buffer<T>(&self,
+                                                stage: u8,
+                                                vbh: &DynamicVertexBuffer<T>,
+                                                access: Access) {
+        unsafe {
+            bgfx_sys::bgfx_set_compute_dynamic_vertex_buffer(stage,
+                                                             vbh.handle,
+                                                             access as bgfx_sys::bgfx_access_t)
+        }
+    }
+
+    /// Dispatches a compute program with the given number of thread groups. Returns the number of
+    /// draw calls used.
+    #[inline]
+    pub fn dispatch(&self,
+                    view: u8,
+                    program: &ComputeProgram,
+                    num_x: u16,
+                    num_y: u16,
+                    num_z: u16)
+                    -> u32 {
+        unsafe {
+            bgfx_sys::bgfx_dispatch(view,
+                                    program.handle,
+                                    num_x,
+                                    num_y,
+                                    num_z,
+                                    bgfx_sys::BGFX_SUBMIT_EYE_FIRST)
+        }
+    }
+
+    /// Sets an indirect buffer as a compute buffer, allowing a compute program to write draw
+    /// arguments into it.
+    #[inline]
+    pub fn set_compute_indirect_buffer(&self, stage: u8, ibh: &IndirectBuffer, access: Access) {
+        unsafe {
+            bgfx_sys::bgfx_set_compute_indirect_buffer(stage,
+                                                       ibh.handle,
+                                                       access as bgfx_sys::bgfx_access_t)
+        }
+    }
+
+    /// Dispatches a compute program, reading the number of thread groups from `num` entries of
+    /// the indirect buffer, starting at `start`. Returns the number of draw calls used.
+    ///
+    /// Fails with `BgfxError::OutOfBounds` if the entries don't fit within the indirect buffer.
+    #[inline]
+    pub fn dispatch_indirect(&self,
+                             view: u8,
+                             program: &ComputeProgram,
+                             indirect: &IndirectBuffer,
+                             start: u16,
+                             num: u16)
+                             -> Result<u32, BgfxError> {
+        indirect.check_range(start, num)?;
+
+        unsafe {
+            Ok(bgfx_sys::bgfx_dispatch_indirect(view,
+                                                program.handle,
+                                                indirect.handle,
+                                                start,
+                                                num,
+                                                bgfx_sys::BGFX_SUBMIT_EYE_FIRST))
+        }
+    }
+
+    /// Submit primitives for rendering, reading the draw arguments from `num` entries of the
+    /// indirect buffer, starting at `start`. Returns the number of draw calls used.
+    ///
+    /// Fails with `BgfxError::OutOfBounds` if the entries don't fit within the indirect buffer.
+    #[inline]
+    pub fn submit_indirect(&self,
+                           view: u8,
+                           program: &Program,
+                           indirect: &IndirectBuffer,
+                           start: u16,
+                           num: u16)
+                           -> Result<u32, BgfxError> {
+        indirect.check_range(start, num)?;
+
+        unsafe {
+            Ok(bgfx_sys::bgfx_submit_indirect(view,
+                                              program.handle,
+                                              indirect.handle,
+                                              start,
+                                              num,
+                                              0,
+                                              false))
+        }
+    }
+
     /// Touches a view. ( ͡° ͜ʖ ͡°)
     #[inline]
     pub fn touch(&self, id: u8) {
@@ -1240,22 +3596,38 @@ impl PlatformData {
 ///
 /// This must be called on the main thread after setting the platform data. See [`PlatformData`].
 ///
+/// If `callback` is `None`, a callback behaving like bgfx' built-in one is used, which prints trace
+/// output to stdout in debug builds of bgfx. A callback is always handed to bgfx, since screen
+/// shots requested through [`Bgfx::request_screenshot`] are delivered through it. Either way,
+/// fatal errors are queued for [`Bgfx::take_errors`].
+///
+/// If `allocator` is `None`, bgfx uses its built-in allocator. See [`Allocator`].
+///
 /// [`PlatformData`]: struct.PlatformData.html
+/// [`Callback`]: callback/trait.Callback.html
+/// [`Bgfx::request_screenshot`]: struct.Bgfx.html#method.request_screenshot
+/// [`Bgfx::take_errors`]: struct.Bgfx.html#method.take_errors
+/// [`Allocator`]: allocator/trait.Allocator.html
 pub fn init(renderer: RendererType,
             vendor_id: Option<VendorId>,
-            device_id: Option<u16>)
+            device_id: Option<u16>,
+            callback: Option<Box<dyn Callback>>,
+            allocator: Option<Box<dyn Allocator>>)
             -> Result<Bgfx, BgfxError> {
     let renderer = renderer as bgfx_sys::bgfx_renderer_type_t;
     let vendor = vendor_id.unwrap_or(Default::default());
     let device = device_id.unwrap_or(0);
+    let mut callback = CallbackShim::new(callback);
+    let mut allocator = allocator.map(AllocatorShim::new);
+    let allocator_ptr = allocator.as_mut().map_or(ptr::null_mut(), |shim| shim.as_ptr());
 
     unsafe {
         let success = bgfx_sys::bgfx_init(renderer,
                                           vendor as u16,
                                           device,
-                                          ptr::null_mut(),
-                                          ptr::null_mut());
+                                          callback.as_ptr(),
+                                          allocator_ptr);
 
-        if success { Ok(Bgfx::new()) } else { Err(BgfxError::InitFailed) }
+        if success { Ok(Bgfx::new(callback, allocator)) } else { Err(BgfxError::InitFailed) }
     }
 }
@@ -0,0 +1,189 @@
+// Copyright (c) 2015-2016, Johan Sköld.
+// License: http://opensource.org/licenses/ISC
+
+//! Integration with the [`log`] crate.
+//!
+//! Only available with the `log` feature enabled.
+//!
+//! [`log`]: https://docs.rs/log
+
+use {Callback, Fatal};
+use callback::DefaultCallback;
+
+/// Target bgfx messages are logged under.
+const TARGET: &str = "bgfx";
+
+/// [`Callback`] routing bgfx' messages into the `log` crate.
+///
+/// Trace output is logged at the debug level, and fatal errors at the error level, both under the
+/// `bgfx` target.
+///
+/// Wrap another callback with [`with_callback`] to keep its shader cache or screen shots while
+/// logging through `log`. That callback still sees the fatal errors and trace output after they
+/// are logged.
+///
+/// # Example
+///
+/// ```no_run
+/// let bgfx = bgfx::init(bgfx::RendererType::Default,
+///                       None,
+///                       None,
+///                       Some(Box::new(bgfx::LogCallback::new())),
+///                       None)
+///     .expect("Failed to initialize bgfx");
+/// ```
+///
+/// [`Callback`]: ../callback/trait.Callback.html
+/// [`with_callback`]: #method.with_callback
+#[derive(Default)]
+pub struct LogCallback {
+    callback: Option<Box<dyn Callback>>,
+}
+
+impl LogCallback {
+
+    /// Creates a callback logging bgfx' messages.
+    #[inline]
+    pub fn new() -> LogCallback {
+        LogCallback { callback: None }
+    }
+
+    /// Creates a callback logging bgfx' messages, and forwarding all callbacks to `callback`.
+    #[inline]
+    pub fn with_callback(callback: Box<dyn Callback>) -> LogCallback {
+        LogCallback { callback: Some(callback) }
+    }
+
+}
+
+impl ::callback::Layer for LogCallback {
+
+    #[inline]
+    fn inner(&self) -> &dyn Callback {
+        match self.callback {
+            Some(ref callback) => &**callback,
+            None => &DefaultCallback,
+        }
+    }
+
+    fn fatal(&self, code: Fatal, message: &str) {
+        error!(target: TARGET, "Fatal error ({:?}): {}", code, message.trim_end());
+
+        if let Some(ref callback) = self.callback {
+            callback.fatal(code, message);
+        }
+    }
+
+    fn trace_vargs(&self, file_path: &str, line: u16, message: &str) {
+        debug!(target: TARGET, "{}:{}: {}", file_path, line, message.trim_end());
+
+        if let Some(ref callback) = self.callback {
+            callback.trace_vargs(file_path, line, message);
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::sync::{Arc, Mutex, MutexGuard, Once};
+
+    use log::{self, Level, LevelFilter, Log, Metadata, Record};
+
+    use super::*;
+
+    /// Records logged by the tests, as level, target and message.
+    static RECORDS: Mutex<Vec<(Level, String, String)>> = Mutex::new(Vec::new());
+
+    /// Serializes the tests, since they share the global logger.
+    static LOCK: Mutex<()> = Mutex::new(());
+
+    struct CapturingLogger;
+
+    impl Log for CapturingLogger {
+
+        fn enabled(&self, _: &Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &Record) {
+            let entry = (record.level(), record.target().to_owned(), record.args().to_string());
+            RECORDS.lock().unwrap().push(entry);
+        }
+
+        fn flush(&self) {}
+
+    }
+
+    static LOGGER: CapturingLogger = CapturingLogger;
+
+    /// Installs the capturing logger, and clears the records of previous tests.
+    fn capture() -> MutexGuard<'static, ()> {
+        static INIT: Once = Once::new();
+        INIT.call_once(|| {
+            log::set_logger(&LOGGER).unwrap();
+            log::set_max_level(LevelFilter::Trace);
+        });
+
+        let guard = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        RECORDS.lock().unwrap().clear();
+        guard
+    }
+
+    fn records() -> Vec<(Level, String, String)> {
+        RECORDS.lock().unwrap().clone()
+    }
+
+    /// Callback remembering the fatal errors and trace output it receives.
+    #[derive(Default)]
+    struct Recorder {
+        calls: Mutex<Vec<String>>,
+    }
+
+    impl Callback for Recorder {
+
+        fn fatal(&self, code: Fatal, message: &str) {
+            self.calls.lock().unwrap().push(format!("fatal {:?} {}", code, message));
+        }
+
+        fn trace_vargs(&self, file_path: &str, line: u16, message: &str) {
+            self.calls.lock().unwrap().push(format!("trace {} {} {}", file_path, line, message));
+        }
+
+    }
+
+    #[test]
+    fn logs_fatal_errors_at_error_level() {
+        let _guard = capture();
+        LogCallback::new().fatal(Fatal::InvalidShader, "Broken shader\n");
+
+        assert_eq!(records(),
+                   [(Level::Error,
+                     "bgfx".to_owned(),
+                     "Fatal error (InvalidShader): Broken shader".to_owned())]);
+    }
+
+    #[test]
+    fn logs_traces_at_debug_level_with_location() {
+        let _guard = capture();
+        LogCallback::new().trace_vargs("src/bgfx.cpp", 42, "Init...\n");
+
+        assert_eq!(records(),
+                   [(Level::Debug, "bgfx".to_owned(), "src/bgfx.cpp:42: Init...".to_owned())]);
+    }
+
+    #[test]
+    fn forwards_to_inner_callback() {
+        let _guard = capture();
+        let recorder = Arc::new(Recorder::default());
+        let callback = LogCallback::with_callback(Box::new(recorder.clone()));
+        callback.fatal(Fatal::DeviceLost, "Lost\n");
+        callback.trace_vargs("src/bgfx.cpp", 7, "Frame\n");
+
+        assert_eq!(records().len(), 2);
+        assert_eq!(*recorder.calls.lock().unwrap(),
+                   ["fatal DeviceLost Lost\n", "trace src/bgfx.cpp 7 Frame\n"]);
+    }
+
+}
@@ -0,0 +1,365 @@
+// Copyright (c) 2015-2016, Johan Sköld.
+// License: http://opensource.org/licenses/ISC
+
+//! Screen shots.
+//!
+//! Screen shots are requested through [`Bgfx::request_screenshot`], and delivered a few frames
+//! later to a [`ScreenshotTarget`]: either a PNG file, or a closure receiving the raw pixels.
+//!
+//! [`Bgfx::request_screenshot`]: ../struct.Bgfx.html#method.request_screenshot
+//! [`ScreenshotTarget`]: enum.ScreenshotTarget.html
+
+use std::cmp;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use BgfxError;
+
+/// Contents of the backbuffer or frame buffer a screen shot was taken of.
+#[derive(Debug, Clone)]
+pub struct Screenshot {
+    /// Width, in pixels.
+    pub width: u32,
+
+    /// Height, in pixels.
+    pub height: u32,
+
+    /// Number of bytes between the starts of two rows.
+    pub pitch: u32,
+
+    /// Pixel data, in BGRA8 format.
+    pub data: Vec<u8>,
+
+    /// Whether the rows are stored bottom to top.
+    pub yflip: bool,
+}
+
+impl Screenshot {
+
+    /// Converts the pixels to tightly packed RGBA8, with rows stored top to bottom.
+    pub fn to_rgba(&self) -> Vec<u8> {
+        let row_size = self.width as usize * 4;
+        let mut rgba = Vec::with_capacity(row_size * self.height as usize);
+
+        for y in 0..self.height as usize {
+            let row = if self.yflip { self.height as usize - 1 - y } else { y };
+            let start = row * self.pitch as usize;
+            let bgra = self.data.get(start..start + row_size).unwrap_or(&[]);
+
+            for pixel in bgra.chunks(4) {
+                rgba.extend_from_slice(&[pixel[2], pixel[1], pixel[0], pixel[3]]);
+            }
+        }
+
+        // Pad out rows missing from the data, so the image always has the advertised size.
+        rgba.resize(row_size * self.height as usize, 0);
+        rgba
+    }
+
+    /// Encodes the screen shot as an uncompressed PNG image.
+    pub fn write_png<W: Write>(&self, writer: W) -> io::Result<()> {
+        write_png(writer, self.width, self.height, &self.to_rgba())
+    }
+
+    /// Saves the screen shot as an uncompressed PNG file.
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        self.write_png(&mut file)?;
+        file.flush()
+    }
+
+}
+
+/// Where a requested screen shot is delivered.
+///
+/// Paths convert into `ScreenshotTarget::Png`, so they can be passed to
+/// [`Bgfx::request_screenshot`] directly.
+///
+/// [`Bgfx::request_screenshot`]: ../struct.Bgfx.html#method.request_screenshot
+pub enum ScreenshotTarget {
+    /// Save as a PNG file at the given path. Errors writing the file are returned from
+    /// [`Bgfx::take_errors`].
+    ///
+    /// [`Bgfx::take_errors`]: ../struct.Bgfx.html#method.take_errors
+    Png(PathBuf),
+
+    /// Pass to a closure. The closure is called on the render thread.
+    Closure(Box<dyn FnOnce(Screenshot) + Send>),
+}
+
+impl ScreenshotTarget {
+
+    /// Creates a target passing the screen shot to `f`.
+    #[inline]
+    pub fn closure<F>(f: F) -> ScreenshotTarget
+        where F: FnOnce(Screenshot) + Send + 'static
+    {
+        ScreenshotTarget::Closure(Box::new(f))
+    }
+
+    pub(crate) fn deliver(self, screenshot: Screenshot) -> Result<(), BgfxError> {
+        match self {
+            ScreenshotTarget::Png(path) => {
+                screenshot.save_png(&path)
+                    .map_err(|error| BgfxError::ScreenshotFailed { path: path, error: error })
+            }
+            ScreenshotTarget::Closure(f) => {
+                f(screenshot);
+                Ok(())
+            }
+        }
+    }
+
+    /// Path of the PNG file, if this is a `Png` target.
+    fn into_path(self) -> Option<PathBuf> {
+        match self {
+            ScreenshotTarget::Png(path) => Some(path),
+            ScreenshotTarget::Closure(_) => None,
+        }
+    }
+
+    /// Error to report when bgfx never answers the request for this target.
+    pub(crate) fn lost(self) -> BgfxError {
+        BgfxError::ScreenshotLost { path: self.into_path() }
+    }
+
+}
+
+impl fmt::Debug for ScreenshotTarget {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ScreenshotTarget::Png(ref path) => f.debug_tuple("Png").field(path).finish(),
+            ScreenshotTarget::Closure(_) => f.write_str("Closure"),
+        }
+    }
+}
+
+impl<'a> From<&'a str> for ScreenshotTarget {
+    #[inline]
+    fn from(path: &'a str) -> ScreenshotTarget {
+        ScreenshotTarget::Png(path.into())
+    }
+}
+
+impl<'a> From<&'a Path> for ScreenshotTarget {
+    #[inline]
+    fn from(path: &'a Path) -> ScreenshotTarget {
+        ScreenshotTarget::Png(path.into())
+    }
+}
+
+impl From<PathBuf> for ScreenshotTarget {
+    #[inline]
+    fn from(path: PathBuf) -> ScreenshotTarget {
+        ScreenshotTarget::Png(path)
+    }
+}
+
+/// Largest amount of data a stored deflate block can hold.
+const MAX_STORED_BLOCK: usize = 65535;
+
+/// Writes tightly packed RGBA8 pixels as a PNG image, using stored (uncompressed) deflate blocks.
+fn write_png<W: Write>(mut writer: W, width: u32, height: u32, rgba: &[u8]) -> io::Result<()> {
+    writer.write_all(b"\x89PNG\r\n\x1a\n")?;
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    // 8 bits per channel, RGBA, deflate, adaptive filtering, no interlacing.
+    header.extend_from_slice(&[8, 6, 0, 0, 0]);
+    write_chunk(&mut writer, b"IHDR", &header)?;
+
+    // Each scanline starts with its filter type, which is always 0 (none).
+    let row_size = width as usize * 4;
+    let mut raw = Vec::with_capacity((row_size + 1) * height as usize);
+    for row in rgba.chunks(row_size.max(1)).take(height as usize) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+
+    let num_blocks = cmp::max(1, raw.len().div_ceil(MAX_STORED_BLOCK));
+    let mut zlib = Vec::with_capacity(raw.len() + num_blocks * 5 + 6);
+    zlib.extend_from_slice(&[0x78, 0x01]);
+
+    for i in 0..num_blocks {
+        let block = &raw[(i * MAX_STORED_BLOCK).min(raw.len())..
+                         ((i + 1) * MAX_STORED_BLOCK).min(raw.len())];
+        let len = block.len() as u16;
+        zlib.push(if i + 1 == num_blocks { 1 } else { 0 });
+        zlib.extend_from_slice(&len.to_le_bytes());
+        zlib.extend_from_slice(&(!len).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+
+    zlib.extend_from_slice(&adler32(&raw).to_be_bytes());
+    write_chunk(&mut writer, b"IDAT", &zlib)?;
+    write_chunk(&mut writer, b"IEND", &[])
+}
+
+fn write_chunk<W: Write>(writer: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    writer.write_all(&(data.len() as u32).to_be_bytes())?;
+    writer.write_all(kind)?;
+    writer.write_all(data)?;
+
+    let crc = crc32(crc32(!0, kind), data);
+    writer.write_all(&(!crc).to_be_bytes())
+}
+
+/// Updates a CRC-32 (as used by PNG) with `data`. Start with `!0`, and invert the final value.
+fn crc32(mut crc: u32, data: &[u8]) -> u32 {
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { 0xedb8_8320 ^ (crc >> 1) } else { crc >> 1 };
+        }
+    }
+    crc
+}
+
+/// Adler-32 checksum, as used by zlib.
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+
+    // 5552 is the largest number of bytes that can be summed before `b` may overflow.
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Splits a PNG file into its chunks, checking the signature and each chunk's CRC.
+    fn chunks(png: &[u8]) -> Vec<([u8; 4], Vec<u8>)> {
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+
+        let mut chunks = Vec::new();
+        let mut rest = &png[8..];
+        while !rest.is_empty() {
+            let len = u32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]) as usize;
+            let kind = [rest[4], rest[5], rest[6], rest[7]];
+            let data = &rest[8..8 + len];
+            let crc = &rest[8 + len..12 + len];
+            assert_eq!(crc, &(!crc32(crc32(!0, &kind), data)).to_be_bytes());
+
+            chunks.push((kind, data.to_vec()));
+            rest = &rest[12 + len..];
+        }
+        chunks
+    }
+
+    /// Inflates a zlib stream made of stored blocks, checking the block headers and checksum.
+    fn inflate_stored(zlib: &[u8]) -> Vec<u8> {
+        assert_eq!(&zlib[..2], &[0x78, 0x01]);
+
+        let mut output = Vec::new();
+        let mut rest = &zlib[2..];
+        loop {
+            let last = rest[0] == 1;
+            let len = u16::from_le_bytes([rest[1], rest[2]]);
+            assert_eq!(!len, u16::from_le_bytes([rest[3], rest[4]]));
+
+            output.extend_from_slice(&rest[5..5 + len as usize]);
+            rest = &rest[5 + len as usize..];
+            if last {
+                break;
+            }
+        }
+
+        assert_eq!(rest, &adler32(&output).to_be_bytes());
+        output
+    }
+
+    fn encode(width: u32, height: u32, rgba: &[u8]) -> (Vec<u8>, Vec<u8>) {
+        let mut png = Vec::new();
+        write_png(&mut png, width, height, rgba).unwrap();
+
+        let chunks = chunks(&png);
+        let kinds: Vec<&[u8]> = chunks.iter().map(|c| &c.0[..]).collect();
+        assert_eq!(kinds, [b"IHDR", b"IDAT", b"IEND"]);
+
+        (chunks[0].1.clone(), inflate_stored(&chunks[1].1))
+    }
+
+    #[test]
+    fn checksums_match_reference_values() {
+        assert_eq!(!crc32(!0, b"123456789"), 0xcbf4_3926);
+        assert_eq!(!crc32(!0, b""), 0);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+        assert_eq!(adler32(b""), 1);
+
+        // Long runs of 0xff exercise the modulo reduction.
+        let ones = vec![0xff; 100_000];
+        let (mut a, mut b) = (1u64, 0u64);
+        for &byte in &ones {
+            a = (a + byte as u64) % 65521;
+            b = (b + a) % 65521;
+        }
+        assert_eq!(adler32(&ones), ((b << 16) | a) as u32);
+    }
+
+    #[test]
+    fn png_prefixes_rows_with_filter_type() {
+        let rgba: Vec<u8> = (0..2 * 3 * 4).map(|i| i as u8).collect();
+        let (header, raw) = encode(2, 3, &rgba);
+
+        assert_eq!(header, [0, 0, 0, 2, 0, 0, 0, 3, 8, 6, 0, 0, 0]);
+        assert_eq!(raw.len(), 3 * (1 + 8));
+        for (row, scanline) in raw.chunks(9).enumerate() {
+            assert_eq!(scanline[0], 0);
+            assert_eq!(&scanline[1..], &rgba[row * 8..row * 8 + 8]);
+        }
+    }
+
+    #[test]
+    fn png_splits_large_images_into_stored_blocks() {
+        // 300 rows of 1 + 400 bytes is a little over 120000 bytes, so it needs two blocks.
+        let rgba: Vec<u8> = (0..100 * 300 * 4).map(|i| (i * 7) as u8).collect();
+        let (_, raw) = encode(100, 300, &rgba);
+        assert_eq!(raw.len(), 300 * 401);
+        assert_eq!(&raw[1..401], &rgba[..400]);
+        assert_eq!(&raw[raw.len() - 400..], &rgba[rgba.len() - 400..]);
+    }
+
+    #[test]
+    fn png_of_empty_image() {
+        let (header, raw) = encode(0, 0, &[]);
+        assert_eq!(&header[..8], &[0; 8]);
+        assert!(raw.is_empty());
+    }
+
+    #[test]
+    fn to_rgba_swizzles_flips_and_skips_padding() {
+        // 2x2 BGRA pixels, with 4 bytes of padding at the end of each row.
+        let screenshot = Screenshot {
+            width: 2,
+            height: 2,
+            pitch: 12,
+            data: vec![1, 2, 3, 4, 5, 6, 7, 8, 0, 0, 0, 0,
+                       9, 10, 11, 12, 13, 14, 15, 16, 0, 0, 0, 0],
+            yflip: false,
+        };
+        assert_eq!(screenshot.to_rgba(),
+                   [3, 2, 1, 4, 7, 6, 5, 8, 11, 10, 9, 12, 15, 14, 13, 16]);
+
+        let flipped = Screenshot { yflip: true, ..screenshot.clone() };
+        assert_eq!(flipped.to_rgba(),
+                   [11, 10, 9, 12, 15, 14, 13, 16, 3, 2, 1, 4, 7, 6, 5, 8]);
+
+        // Rows missing from the data are filled with zeroes.
+        let truncated = Screenshot { data: screenshot.data[..12].to_vec(), ..screenshot };
+        assert_eq!(truncated.to_rgba(), [3, 2, 1, 4, 7, 6, 5, 8, 0, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+}
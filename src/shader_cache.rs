@@ -0,0 +1,577 @@
+// Copyright (c) 2015-2016, Johan Sköld.
+// License: http://opensource.org/licenses/ISC
+
+//! Persistent shader cache.
+//!
+//! bgfx compiles shaders and programs for some renderers (Direct3D in particular) at load time,
+//! and asks its callback to cache the results by a 64-bit id. [`DiskShaderCache`] stores those
+//! results in a directory, so they survive between runs.
+//!
+//! [`DiskShaderCache`]: struct.DiskShaderCache.html
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::{Mutex, MutexGuard};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::SystemTime;
+
+use Callback;
+use callback::DefaultCallback;
+
+/// Identifies a cache entry file, and the version of its layout.
+const MAGIC: &[u8; 8] = b"BGFXSC01";
+
+/// Size of the header in front of each entry: magic, payload size and payload checksum.
+const HEADER_SIZE: usize = 8 + 4 + 8;
+
+/// Extension of entry files.
+const EXTENSION: &str = "bin";
+
+/// Extension of files being written. Leftovers of interrupted writes are removed on startup.
+const TEMP_EXTENSION: &str = "tmp";
+
+/// [`Callback`] caching compiled shaders and programs in a directory.
+///
+/// Each entry is stored in its own file, named after its id. Entries are written to a temporary
+/// file first and then renamed into place, so readers never see a partially written entry. Each
+/// file carries a checksum of its contents, which also catches entries cut short by a crash;
+/// entries that fail to load are deleted and reported as missing, so bgfx simply compiles them
+/// again.
+///
+/// When the total size of the entries exceeds the configured maximum, the least recently used
+/// entries are evicted. The last access time is kept in the file modification times, so the order
+/// carries over to the next run.
+///
+/// Traces, screen shots and other events not related to the cache go to the callback given to
+/// [`with_callback`]. Without one, they get the same treatment as when bgfx has no callback.
+///
+/// # Example
+///
+/// ```no_run
+/// let cache = bgfx::DiskShaderCache::new("shader-cache", 64 * 1024 * 1024)
+///     .expect("Failed to open shader cache");
+/// let bgfx = bgfx::init(bgfx::RendererType::Default,
+///                       None,
+///                       None,
+///                       Some(Box::new(cache)),
+///                       None)
+///     .expect("Failed to initialize bgfx");
+/// ```
+///
+/// [`Callback`]: ../callback/trait.Callback.html
+/// [`with_callback`]: #method.with_callback
+pub struct DiskShaderCache {
+    dir: PathBuf,
+    max_size: u64,
+    index: Mutex<Index>,
+    next_temp: AtomicUsize,
+    callback: Box<dyn Callback>,
+}
+
+/// In-memory view of the entries in the cache directory.
+#[derive(Debug, Default)]
+struct Index {
+    entries: HashMap<u64, Entry>,
+    total_size: u64,
+
+    /// Incremented on every access, to order the entries by recency.
+    clock: u64,
+
+    /// Payload loaded by `cache_read_size`, handed out by the `cache_read` that follows it.
+    loaded: Option<(u64, Vec<u8>)>,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Entry {
+    /// Size of the file, including the header.
+    size: u64,
+    last_used: u64,
+}
+
+impl DiskShaderCache {
+
+    /// Opens the cache in `dir`, creating the directory if needed. The total size of the cached
+    /// entries is kept below `max_size` bytes.
+    pub fn new<P: Into<PathBuf>>(dir: P, max_size: u64) -> io::Result<DiskShaderCache> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+
+        let mut found = Vec::new();
+        for dir_entry in fs::read_dir(&dir)? {
+            let dir_entry = dir_entry?;
+            let path = dir_entry.path();
+            let metadata = match dir_entry.metadata() {
+                Ok(metadata) if metadata.is_file() => metadata,
+                _ => continue,
+            };
+
+            let extension = path.extension().and_then(|ext| ext.to_str());
+            if extension == Some(TEMP_EXTENSION) {
+                let _ = fs::remove_file(&path);
+                continue;
+            }
+
+            if extension != Some(EXTENSION) {
+                continue;
+            }
+
+            if let Some(id) = parse_id(&path) {
+                let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                found.push((modified, id, metadata.len()));
+            }
+        }
+
+        // Number the existing entries from least to most recently used.
+        found.sort();
+
+        let mut index = Index::default();
+        for (_, id, size) in found {
+            index.clock += 1;
+            index.total_size += size;
+            index.entries.insert(id, Entry { size: size, last_used: index.clock });
+        }
+
+        let cache = DiskShaderCache {
+            dir: dir,
+            max_size: max_size,
+            index: Mutex::new(index),
+            next_temp: AtomicUsize::new(0),
+            callback: Box::new(DefaultCallback),
+        };
+
+        cache.evict(&mut cache.index());
+        Ok(cache)
+    }
+
+    /// Opens the cache in `dir` like [`new`], and forwards all non-cache callbacks to
+    /// `callback`.
+    ///
+    /// [`new`]: #method.new
+    pub fn with_callback<P: Into<PathBuf>>(dir: P,
+                                           max_size: u64,
+                                           callback: Box<dyn Callback>)
+                                           -> io::Result<DiskShaderCache> {
+        Ok(DiskShaderCache { callback: callback, ..DiskShaderCache::new(dir, max_size)? })
+    }
+
+    /// Directory the entries are stored in.
+    #[inline]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Maximum total size of the entries, in bytes.
+    #[inline]
+    pub fn max_size(&self) -> u64 {
+        self.max_size
+    }
+
+    /// Current total size of the entries, in bytes.
+    pub fn size(&self) -> u64 {
+        self.index().total_size
+    }
+
+    /// Number of entries in the cache.
+    pub fn len(&self) -> usize {
+        self.index().entries.len()
+    }
+
+    /// Whether the cache has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.index().entries.is_empty()
+    }
+
+    /// Removes all entries from the cache.
+    pub fn clear(&self) {
+        let mut index = self.index();
+        for id in index.entries.keys() {
+            let _ = fs::remove_file(self.path(*id));
+        }
+
+        index.entries.clear();
+        index.total_size = 0;
+        index.loaded = None;
+    }
+
+    fn index(&self) -> MutexGuard<'_, Index> {
+        self.index.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn path(&self, id: u64) -> PathBuf {
+        self.dir.join(format!("{:016x}.{}", id, EXTENSION))
+    }
+
+    /// Loads and verifies the payload of an entry. Entries that fail to load are removed.
+    fn load(&self, index: &mut Index, id: u64) -> Option<Vec<u8>> {
+        if !index.entries.contains_key(&id) {
+            return None;
+        }
+
+        let path = self.path(id);
+        match read_entry(&path) {
+            Ok(data) => {
+                index.clock += 1;
+                let clock = index.clock;
+                if let Some(entry) = index.entries.get_mut(&id) {
+                    entry.last_used = clock;
+                }
+
+                // Persist the access for the next run. Failing to do so only affects eviction
+                // order.
+                if let Ok(file) = File::options().write(true).open(&path) {
+                    let _ = file.set_modified(SystemTime::now());
+                }
+
+                Some(data)
+            }
+            Err(_) => {
+                self.remove(index, id);
+                None
+            }
+        }
+    }
+
+    fn remove(&self, index: &mut Index, id: u64) {
+        if let Some(entry) = index.entries.remove(&id) {
+            index.total_size -= entry.size;
+        }
+
+        let _ = fs::remove_file(self.path(id));
+    }
+
+    /// Evicts the least recently used entries until the cache fits within its maximum size.
+    fn evict(&self, index: &mut Index) {
+        if index.total_size <= self.max_size {
+            return;
+        }
+
+        let mut entries: Vec<(u64, u64)> = index.entries
+            .iter()
+            .map(|(&id, entry)| (entry.last_used, id))
+            .collect();
+        entries.sort();
+
+        for (_, id) in entries {
+            if index.total_size <= self.max_size {
+                break;
+            }
+            self.remove(index, id);
+        }
+    }
+
+    /// Writes an entry to a new temporary file, returning its path. The file is not synced: if
+    /// it ends up truncated, its checksum no longer matches, and the entry is dropped on load.
+    fn write_temp(&self, id: u64, data: &[u8]) -> io::Result<PathBuf> {
+        let temp = self.dir.join(format!("{:016x}-{}-{}.{}",
+                                         id,
+                                         process::id(),
+                                         self.next_temp.fetch_add(1, Ordering::Relaxed),
+                                         TEMP_EXTENSION));
+
+        match write_entry(&temp, data) {
+            Ok(()) => Ok(temp),
+            Err(err) => {
+                let _ = fs::remove_file(&temp);
+                Err(err)
+            }
+        }
+    }
+
+}
+
+impl ::callback::Layer for DiskShaderCache {
+
+    #[inline]
+    fn inner(&self) -> &dyn Callback {
+        &*self.callback
+    }
+
+    fn cache_read_size(&self, id: u64) -> u32 {
+        let mut index = self.index();
+        match self.load(&mut index, id) {
+            Some(data) => {
+                let size = data.len() as u32;
+                index.loaded = Some((id, data));
+                size
+            }
+            None => 0,
+        }
+    }
+
+    fn cache_read(&self, id: u64, data: &mut [u8]) -> bool {
+        let mut index = self.index();
+        let loaded = match index.loaded.take() {
+            Some((loaded_id, loaded)) if loaded_id == id => Some(loaded),
+            _ => self.load(&mut index, id),
+        };
+
+        match loaded {
+            Some(ref loaded) if loaded.len() == data.len() => {
+                data.copy_from_slice(loaded);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn cache_write(&self, id: u64, data: &[u8]) {
+        let size = (HEADER_SIZE + data.len()) as u64;
+        if size > self.max_size || data.len() > u32::MAX as usize {
+            return;
+        }
+
+        // The entry is written outside the lock, so other cache accesses don't wait for the disk.
+        // Only renaming it into place has to happen in step with the index.
+        let temp = self.write_temp(id, data);
+
+        let mut index = self.index();
+        if index.loaded.as_ref().is_some_and(|&(loaded_id, _)| loaded_id == id) {
+            index.loaded = None;
+        }
+
+        let renamed = temp.and_then(|temp| {
+            let result = fs::rename(&temp, self.path(id));
+            if result.is_err() {
+                let _ = fs::remove_file(&temp);
+            }
+            result
+        });
+
+        if renamed.is_err() {
+            // Whatever was stored under this id before may have been replaced or not; forget it.
+            self.remove(&mut index, id);
+            return;
+        }
+
+        if let Some(old) = index.entries.remove(&id) {
+            index.total_size -= old.size;
+        }
+
+        index.clock += 1;
+        index.total_size += size;
+        let clock = index.clock;
+        index.entries.insert(id, Entry { size: size, last_used: clock });
+
+        self.evict(&mut index);
+    }
+
+}
+
+impl fmt::Debug for DiskShaderCache {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DiskShaderCache")
+            .field("dir", &self.dir)
+            .field("max_size", &self.max_size)
+            .field("index", &*self.index())
+            .finish()
+    }
+}
+
+/// Parses the id out of an entry file name.
+fn parse_id(path: &Path) -> Option<u64> {
+    let stem = path.file_stem().and_then(|stem| stem.to_str())?;
+    if stem.len() != 16 {
+        return None;
+    }
+    u64::from_str_radix(stem, 16).ok()
+}
+
+fn write_entry(path: &Path, data: &[u8]) -> io::Result<()> {
+    let mut file = File::create(path)?;
+    file.write_all(MAGIC)?;
+    file.write_all(&(data.len() as u32).to_le_bytes())?;
+    file.write_all(&checksum(data).to_le_bytes())?;
+    file.write_all(data)
+}
+
+fn read_entry(path: &Path) -> io::Result<Vec<u8>> {
+    let mut contents = Vec::new();
+    File::open(path)?.read_to_end(&mut contents)?;
+
+    let corrupted = || io::Error::new(io::ErrorKind::InvalidData, "Corrupted shader cache entry");
+    if contents.len() < HEADER_SIZE || &contents[..8] != MAGIC {
+        return Err(corrupted());
+    }
+
+    let mut size = [0; 4];
+    let mut sum = [0; 8];
+    size.copy_from_slice(&contents[8..12]);
+    sum.copy_from_slice(&contents[12..HEADER_SIZE]);
+
+    let data = contents.split_off(HEADER_SIZE);
+    if data.len() != u32::from_le_bytes(size) as usize ||
+       checksum(&data) != u64::from_le_bytes(sum) {
+        return Err(corrupted());
+    }
+
+    Ok(data)
+}
+
+/// 64-bit FNV-1a hash, used to detect corrupted entries.
+fn checksum(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::process;
+
+    use super::*;
+    use Callback;
+
+    /// Temporary directory, removed when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+
+        fn new(name: &str) -> TempDir {
+            let name = format!("bgfx-shader-cache-{}-{}", name, process::id());
+            let path = env::temp_dir().join(name);
+            let _ = fs::remove_dir_all(&path);
+            TempDir(path)
+        }
+
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn read(cache: &DiskShaderCache, id: u64) -> Option<Vec<u8>> {
+        let size = cache.cache_read_size(id);
+        if size == 0 {
+            return None;
+        }
+
+        let mut data = vec![0; size as usize];
+        assert!(cache.cache_read(id, &mut data));
+        Some(data)
+    }
+
+    #[test]
+    fn stores_and_accounts_entries() {
+        let dir = TempDir::new("store");
+        let cache = DiskShaderCache::new(&dir.0, 1024).unwrap();
+        assert!(cache.is_empty());
+        assert_eq!(read(&cache, 1), None);
+
+        cache.cache_write(1, b"first");
+        cache.cache_write(2, b"second entry");
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.size(), (2 * HEADER_SIZE + 5 + 12) as u64);
+        assert_eq!(read(&cache, 1).unwrap(), b"first");
+        assert_eq!(read(&cache, 2).unwrap(), b"second entry");
+
+        // Overwriting replaces the size of the old entry.
+        cache.cache_write(1, b"1st");
+        assert_eq!(cache.size(), (2 * HEADER_SIZE + 3 + 12) as u64);
+        assert_eq!(read(&cache, 1).unwrap(), b"1st");
+
+        // A reopened cache finds the entries again.
+        drop(cache);
+        let cache = DiskShaderCache::new(&dir.0, 1024).unwrap();
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.size(), (2 * HEADER_SIZE + 3 + 12) as u64);
+        assert_eq!(read(&cache, 2).unwrap(), b"second entry");
+
+        cache.clear();
+        assert!(cache.is_empty());
+        assert_eq!(cache.size(), 0);
+        assert_eq!(fs::read_dir(&dir.0).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let dir = TempDir::new("evict");
+        let entry_size = (HEADER_SIZE + 10) as u64;
+        let cache = DiskShaderCache::new(&dir.0, 3 * entry_size).unwrap();
+
+        cache.cache_write(1, &[1; 10]);
+        cache.cache_write(2, &[2; 10]);
+        cache.cache_write(3, &[3; 10]);
+        assert_eq!(cache.size(), 3 * entry_size);
+
+        // Reading 1 makes 2 the least recently used entry.
+        assert!(read(&cache, 1).is_some());
+        cache.cache_write(4, &[4; 10]);
+
+        assert_eq!(cache.len(), 3);
+        assert_eq!(cache.size(), 3 * entry_size);
+        assert_eq!(read(&cache, 2), None);
+        assert!(!cache.path(2).exists());
+        for &id in &[1, 3, 4] {
+            assert_eq!(read(&cache, id).unwrap(), vec![id as u8; 10]);
+        }
+
+        // Entries larger than the whole cache are never stored.
+        cache.cache_write(5, &[5; 100]);
+        assert_eq!(read(&cache, 5), None);
+        assert_eq!(cache.len(), 3);
+
+        // Reopening with a smaller maximum evicts down to it.
+        drop(cache);
+        let cache = DiskShaderCache::new(&dir.0, entry_size).unwrap();
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.size(), entry_size);
+    }
+
+    #[test]
+    fn drops_corrupted_entries() {
+        let dir = TempDir::new("corrupt");
+        let cache = DiskShaderCache::new(&dir.0, 1024).unwrap();
+
+        cache.cache_write(1, b"payload");
+        cache.cache_write(2, b"payload");
+        cache.cache_write(3, b"payload");
+
+        // Flip a payload byte, truncate a file, and break a magic.
+        let mut contents = fs::read(cache.path(1)).unwrap();
+        *contents.last_mut().unwrap() ^= 0xff;
+        fs::write(cache.path(1), &contents).unwrap();
+        fs::write(cache.path(2), &contents[..HEADER_SIZE + 2]).unwrap();
+        contents[0] = b'X';
+        fs::write(cache.path(3), &contents).unwrap();
+
+        for &id in &[1, 2, 3] {
+            assert_eq!(read(&cache, id), None);
+            assert!(!cache.path(id).exists());
+        }
+        assert!(cache.is_empty());
+        assert_eq!(cache.size(), 0);
+    }
+
+    #[test]
+    fn ignores_foreign_files_and_removes_temporaries() {
+        let dir = TempDir::new("foreign");
+        fs::create_dir_all(&dir.0).unwrap();
+        fs::write(dir.0.join("0000000000000001-1-0.tmp"), b"partial").unwrap();
+        fs::write(dir.0.join("readme.txt"), b"not an entry").unwrap();
+        fs::write(dir.0.join("short.bin"), b"not an entry").unwrap();
+
+        let cache = DiskShaderCache::new(&dir.0, 1024).unwrap();
+        assert!(cache.is_empty());
+        assert!(!dir.0.join("0000000000000001-1-0.tmp").exists());
+        assert!(dir.0.join("readme.txt").exists());
+
+        cache.cache_write(1, b"data");
+        let names: Vec<_> = fs::read_dir(&dir.0)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .filter(|name| name.ends_with(".tmp"))
+            .collect();
+        assert!(names.is_empty());
+    }
+
+}
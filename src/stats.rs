@@ -0,0 +1,349 @@
+// Copyright (c) 2015-2016, Johan Sköld.
+// License: http://opensource.org/licenses/ISC
+
+//! Frame statistics.
+//!
+//! [`Bgfx::stats`] returns the statistics of the last frame. Feed them into a [`StatsHistory`] to
+//! get frame time summaries over a number of frames.
+//!
+//! [`Bgfx::stats`]: ../struct.Bgfx.html#method.stats
+//! [`StatsHistory`]: struct.StatsHistory.html
+
+use std::collections::VecDeque;
+use std::collections::vec_deque;
+use std::time::Duration;
+
+use bgfx_sys;
+
+/// Statistics of a single frame, as returned by [`Bgfx::stats`].
+///
+/// [`Bgfx::stats`]: ../struct.Bgfx.html#method.stats
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct Stats {
+    /// CPU time spent on the frame.
+    pub cpu_time: Duration,
+
+    /// GPU time spent on the frame. Zero if the renderer doesn't support GPU timers.
+    pub gpu_time: Duration,
+
+    /// Time the render thread spent waiting for the main thread.
+    pub wait_render: Duration,
+
+    /// Time the main thread spent waiting for the render thread.
+    pub wait_submit: Duration,
+
+    /// Number of draw calls submitted.
+    pub num_draw: u32,
+
+    /// Number of compute calls submitted.
+    pub num_compute: u32,
+
+    /// Maximum observed GPU latency, in frames.
+    pub max_gpu_latency: u32,
+
+    /// Backbuffer width, in pixels.
+    pub width: u16,
+
+    /// Backbuffer height, in pixels.
+    pub height: u16,
+
+    /// Debug text width, in characters.
+    pub text_width: u16,
+
+    /// Debug text height, in characters.
+    pub text_height: u16,
+}
+
+impl Stats {
+
+    pub(crate) fn from_raw(raw: &bgfx_sys::bgfx_stats_t) -> Stats {
+        let cpu_ticks = raw.cpuTimeEnd.saturating_sub(raw.cpuTimeBegin);
+        let gpu_ticks = raw.gpuTimeEnd.saturating_sub(raw.gpuTimeBegin);
+
+        // The wait times are measured with the CPU timer, and may be negative when no wait
+        // happened.
+        let wait_render = if raw.waitRender > 0 { raw.waitRender as u64 } else { 0 };
+        let wait_submit = if raw.waitSubmit > 0 { raw.waitSubmit as u64 } else { 0 };
+
+        Stats {
+            cpu_time: ticks_to_duration(cpu_ticks, raw.cpuTimerFreq),
+            gpu_time: ticks_to_duration(gpu_ticks, raw.gpuTimerFreq),
+            wait_render: ticks_to_duration(wait_render, raw.cpuTimerFreq),
+            wait_submit: ticks_to_duration(wait_submit, raw.cpuTimerFreq),
+            num_draw: raw.numDraw,
+            num_compute: raw.numCompute,
+            max_gpu_latency: raw.maxGpuLatency,
+            width: raw.width,
+            height: raw.height,
+            text_width: raw.textWidth,
+            text_height: raw.textHeight,
+        }
+    }
+
+}
+
+/// Converts a number of timer ticks into a duration. A frequency of 0 means the timer is
+/// unavailable.
+fn ticks_to_duration(ticks: u64, freq: u64) -> Duration {
+    if freq == 0 {
+        return Duration::from_secs(0);
+    }
+
+    let secs = ticks / freq;
+    let nanos = (ticks % freq) as u128 * 1_000_000_000 / freq as u128;
+    Duration::new(secs, nanos as u32)
+}
+
+/// Summary of a duration over the frames in a [`StatsHistory`].
+///
+/// [`StatsHistory`]: struct.StatsHistory.html
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct StatsSummary {
+    pub min: Duration,
+    pub avg: Duration,
+    pub max: Duration,
+
+    /// Median.
+    pub p50: Duration,
+
+    /// 95th percentile.
+    pub p95: Duration,
+
+    /// 99th percentile.
+    pub p99: Duration,
+}
+
+/// Rolling history of the statistics of the last frames.
+///
+/// # Example
+///
+/// ```no_run
+/// # let bgfx: bgfx::Bgfx = unimplemented!();
+/// let mut history = bgfx::StatsHistory::new(120);
+///
+/// loop {
+///     // ...
+///     bgfx.frame();
+///     history.push(bgfx.stats());
+///
+///     if let Some(cpu) = history.cpu_time() {
+///         println!("cpu: {:?} avg, {:?} p95", cpu.avg, cpu.p95);
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct StatsHistory {
+    frames: VecDeque<Stats>,
+    capacity: usize,
+}
+
+impl StatsHistory {
+
+    /// Creates a history keeping the last `capacity` frames.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is 0.
+    pub fn new(capacity: usize) -> StatsHistory {
+        assert!(capacity > 0, "StatsHistory capacity must be non-zero");
+
+        StatsHistory {
+            frames: VecDeque::with_capacity(capacity),
+            capacity: capacity,
+        }
+    }
+
+    /// Adds the statistics of a frame, dropping the oldest frame if the history is full.
+    pub fn push(&mut self, stats: Stats) {
+        if self.frames.len() == self.capacity {
+            self.frames.pop_front();
+        }
+
+        self.frames.push_back(stats);
+    }
+
+    /// Removes all frames from the history.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+
+    /// Maximum number of frames kept.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Number of frames currently in the history.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Whether the history has no frames yet.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Statistics of the most recently pushed frame.
+    #[inline]
+    pub fn latest(&self) -> Option<&Stats> {
+        self.frames.back()
+    }
+
+    /// Iterates over the frames, from oldest to newest.
+    #[inline]
+    pub fn iter(&self) -> vec_deque::Iter<'_, Stats> {
+        self.frames.iter()
+    }
+
+    /// Summarizes the duration selected by `f` over all frames. Returns `None` if the history is
+    /// empty.
+    pub fn summary<F>(&self, f: F) -> Option<StatsSummary>
+        where F: Fn(&Stats) -> Duration
+    {
+        if self.frames.is_empty() {
+            return None;
+        }
+
+        let mut values: Vec<Duration> = self.frames.iter().map(f).collect();
+        values.sort();
+
+        let total: Duration = values.iter().sum();
+
+        Some(StatsSummary {
+            min: values[0],
+            avg: total / values.len() as u32,
+            max: values[values.len() - 1],
+            p50: percentile(&values, 50.0),
+            p95: percentile(&values, 95.0),
+            p99: percentile(&values, 99.0),
+        })
+    }
+
+    /// Returns the `p`th percentile (0 to 100) of the duration selected by `f`, using the
+    /// nearest-rank method. Returns `None` if the history is empty.
+    pub fn percentile<F>(&self, f: F, p: f64) -> Option<Duration>
+        where F: Fn(&Stats) -> Duration
+    {
+        if self.frames.is_empty() {
+            return None;
+        }
+
+        let mut values: Vec<Duration> = self.frames.iter().map(f).collect();
+        values.sort();
+        Some(percentile(&values, p))
+    }
+
+    /// Summary of the CPU frame time.
+    #[inline]
+    pub fn cpu_time(&self) -> Option<StatsSummary> {
+        self.summary(|s| s.cpu_time)
+    }
+
+    /// Summary of the GPU frame time.
+    #[inline]
+    pub fn gpu_time(&self) -> Option<StatsSummary> {
+        self.summary(|s| s.gpu_time)
+    }
+
+}
+
+impl<'a> IntoIterator for &'a StatsHistory {
+    type Item = &'a Stats;
+    type IntoIter = vec_deque::Iter<'a, Stats>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.frames.iter()
+    }
+}
+
+/// Nearest-rank percentile of a sorted, non-empty slice.
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    let p = p.clamp(0.0, 100.0);
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.max(1).min(sorted.len()) - 1]
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::Duration;
+
+    use super::*;
+
+    fn frame(cpu_ms: u64) -> Stats {
+        Stats { cpu_time: Duration::from_millis(cpu_ms), ..Default::default() }
+    }
+
+    #[test]
+    fn ticks_to_duration_handles_frequencies() {
+        assert_eq!(ticks_to_duration(1_500, 1_000), Duration::from_millis(1_500));
+        assert_eq!(ticks_to_duration(3, 1_000_000), Duration::from_micros(3));
+        assert_eq!(ticks_to_duration(u64::MAX, u64::MAX), Duration::from_secs(1));
+        assert_eq!(ticks_to_duration(42, 0), Duration::from_secs(0));
+    }
+
+    #[test]
+    fn percentile_uses_nearest_rank() {
+        let values: Vec<Duration> = (1..=10).map(Duration::from_millis).collect();
+
+        assert_eq!(percentile(&values, 0.0), Duration::from_millis(1));
+        assert_eq!(percentile(&values, 10.0), Duration::from_millis(1));
+        assert_eq!(percentile(&values, 11.0), Duration::from_millis(2));
+        assert_eq!(percentile(&values, 50.0), Duration::from_millis(5));
+        assert_eq!(percentile(&values, 95.0), Duration::from_millis(10));
+        assert_eq!(percentile(&values, 100.0), Duration::from_millis(10));
+        assert_eq!(percentile(&values, 250.0), Duration::from_millis(10));
+        assert_eq!(percentile(&values, -5.0), Duration::from_millis(1));
+        assert_eq!(percentile(&values[..1], 99.0), Duration::from_millis(1));
+    }
+
+    #[test]
+    fn history_drops_oldest_frames() {
+        let mut history = StatsHistory::new(3);
+        assert!(history.is_empty());
+        assert_eq!(history.cpu_time(), None);
+
+        for ms in 1..=5 {
+            history.push(frame(ms));
+        }
+
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.latest(), Some(&frame(5)));
+        let cpu: Vec<u64> = history.iter().map(|s| s.cpu_time.as_millis() as u64).collect();
+        assert_eq!(cpu, [3, 4, 5]);
+
+        history.clear();
+        assert!(history.is_empty());
+        assert_eq!(history.capacity(), 3);
+    }
+
+    #[test]
+    fn summary_of_unsorted_frames() {
+        let mut history = StatsHistory::new(100);
+        for &ms in &[40, 10, 30, 20, 100] {
+            history.push(frame(ms));
+        }
+
+        let summary = history.cpu_time().unwrap();
+        assert_eq!(summary.min, Duration::from_millis(10));
+        assert_eq!(summary.avg, Duration::from_millis(40));
+        assert_eq!(summary.max, Duration::from_millis(100));
+        assert_eq!(summary.p50, Duration::from_millis(30));
+        assert_eq!(summary.p95, Duration::from_millis(100));
+        assert_eq!(summary.p99, Duration::from_millis(100));
+
+        assert_eq!(history.percentile(|s| s.cpu_time, 40.0), Some(Duration::from_millis(20)));
+        assert_eq!(history.gpu_time().unwrap().max, Duration::from_secs(0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_capacity_panics() {
+        StatsHistory::new(0);
+    }
+
+}
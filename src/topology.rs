@@ -0,0 +1,232 @@
+// Copyright (c) 2015-2016, Johan Sköld.
+// License: http://opensource.org/licenses/ISC
+
+//! Index buffer topology helpers.
+//!
+//! [`convert`] turns index lists of one topology into another, such as triangle lists into line
+//! lists for wireframe overlays. [`sort_tri_list`] reorders the triangles of a list, such as back
+//! to front for transparent geometry. Both work on `u16` and `u32` indices.
+//!
+//! Like [`VertexDecl::weld`], these helpers process whole meshes, and take a `&Bgfx` since bgfx
+//! may allocate scratch memory for them through its allocator, which only exists while bgfx is
+//! initialized. Helpers that only convert individual vertices, such as [`VertexDecl::pack`], work
+//! without it.
+//!
+//! [`convert`]: fn.convert.html
+//! [`sort_tri_list`]: fn.sort_tri_list.html
+//! [`VertexDecl::weld`]: ../struct.VertexDecl.html#method.weld
+//! [`VertexDecl::pack`]: ../struct.VertexDecl.html#method.pack
+
+use std::mem;
+use std::os::raw::c_void;
+use std::ptr;
+
+use bgfx_sys;
+
+use {Attrib, AttribType, Bgfx, BgfxError, VertexDecl};
+
+/// Topology conversion.
+#[repr(i32)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum TopologyConvert {
+    /// Flips the winding order of a triangle list.
+    TriListFlipWinding = bgfx_sys::BGFX_TOPOLOGY_CONVERT_TRI_LIST_FLIP_WINDING,
+
+    /// Converts a triangle list into a line list, with each edge appearing once.
+    TriListToLineList = bgfx_sys::BGFX_TOPOLOGY_CONVERT_TRI_LIST_TO_LINE_LIST,
+
+    /// Converts a triangle strip into a triangle list.
+    TriStripToTriList = bgfx_sys::BGFX_TOPOLOGY_CONVERT_TRI_STRIP_TO_TRI_LIST,
+
+    /// Converts a line strip into a line list.
+    LineStripToLineList = bgfx_sys::BGFX_TOPOLOGY_CONVERT_LINE_STRIP_TO_LINE_LIST,
+}
+
+impl TopologyConvert {
+
+    /// Number of indices making up the first primitive of the source topology. bgfx computes the
+    /// output size of strips without checking for shorter input, so it must never see any.
+    #[inline]
+    fn min_indices(self) -> usize {
+        match self {
+            TopologyConvert::TriListFlipWinding | TopologyConvert::TriListToLineList => 1,
+            TopologyConvert::TriStripToTriList => 3,
+            TopologyConvert::LineStripToLineList => 2,
+        }
+    }
+
+}
+
+/// Triangle sort order.
+///
+/// Direction sorts order triangles along the view direction, while distance sorts order them by
+/// their distance to the view position. The `Min`, `Avg` and `Max` suffixes select which vertex
+/// of each triangle is used as its sort key: the nearest, the average, or the farthest.
+#[repr(i32)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum TopologySort {
+    DirectionFrontToBackMin = bgfx_sys::BGFX_TOPOLOGY_SORT_DIRECTION_FRONT_TO_BACK_MIN,
+    DirectionFrontToBackAvg = bgfx_sys::BGFX_TOPOLOGY_SORT_DIRECTION_FRONT_TO_BACK_AVG,
+    DirectionFrontToBackMax = bgfx_sys::BGFX_TOPOLOGY_SORT_DIRECTION_FRONT_TO_BACK_MAX,
+    DirectionBackToFrontMin = bgfx_sys::BGFX_TOPOLOGY_SORT_DIRECTION_BACK_TO_FRONT_MIN,
+    DirectionBackToFrontAvg = bgfx_sys::BGFX_TOPOLOGY_SORT_DIRECTION_BACK_TO_FRONT_AVG,
+    DirectionBackToFrontMax = bgfx_sys::BGFX_TOPOLOGY_SORT_DIRECTION_BACK_TO_FRONT_MAX,
+    DistanceFrontToBackMin = bgfx_sys::BGFX_TOPOLOGY_SORT_DISTANCE_FRONT_TO_BACK_MIN,
+    DistanceFrontToBackAvg = bgfx_sys::BGFX_TOPOLOGY_SORT_DISTANCE_FRONT_TO_BACK_AVG,
+    DistanceFrontToBackMax = bgfx_sys::BGFX_TOPOLOGY_SORT_DISTANCE_FRONT_TO_BACK_MAX,
+    DistanceBackToFrontMin = bgfx_sys::BGFX_TOPOLOGY_SORT_DISTANCE_BACK_TO_FRONT_MIN,
+    DistanceBackToFrontAvg = bgfx_sys::BGFX_TOPOLOGY_SORT_DISTANCE_BACK_TO_FRONT_AVG,
+    DistanceBackToFrontMax = bgfx_sys::BGFX_TOPOLOGY_SORT_DISTANCE_BACK_TO_FRONT_MAX,
+}
+
+mod private {
+    pub trait Sealed {}
+
+    impl Sealed for u16 {}
+    impl Sealed for u32 {}
+}
+
+/// Index type bgfx understands: `u16` or `u32`.
+///
+/// This trait is sealed, and cannot be implemented outside of this crate.
+pub trait Index: private::Sealed + Copy + Default {
+
+    /// Whether the index type is 32 bits wide.
+    #[doc(hidden)]
+    const INDEX32: bool;
+
+    #[doc(hidden)]
+    fn to_usize(self) -> usize;
+
+}
+
+impl Index for u16 {
+
+    const INDEX32: bool = false;
+
+    #[inline]
+    fn to_usize(self) -> usize {
+        self as usize
+    }
+
+}
+
+impl Index for u32 {
+
+    const INDEX32: bool = true;
+
+    #[inline]
+    fn to_usize(self) -> usize {
+        self as usize
+    }
+
+}
+
+/// Converts `indices` to another topology, returning the new indices.
+///
+/// Input too short to hold a single primitive, such as a triangle strip of fewer than 3 indices,
+/// converts to no indices.
+///
+/// # Example
+///
+/// ```no_run
+/// # let bgfx: bgfx::Bgfx = unimplemented!();
+/// let triangles: [u16; 6] = [0, 1, 2, 2, 1, 3];
+/// let lines = bgfx::topology::convert(&bgfx,
+///                                     bgfx::TopologyConvert::TriListToLineList,
+///                                     &triangles);
+/// assert_eq!(lines.len(), 10);
+/// ```
+pub fn convert<I: Index>(_bgfx: &Bgfx, conversion: TopologyConvert, indices: &[I]) -> Vec<I> {
+    if indices.len() < conversion.min_indices() {
+        return Vec::new();
+    }
+
+    let num = indices.len() as u32;
+    let src = indices.as_ptr() as *const c_void;
+    let conversion = conversion as bgfx_sys::bgfx_topology_convert_t;
+
+    unsafe {
+        // Without a destination, bgfx returns an upper bound of the number of output indices.
+        let max =
+            bgfx_sys::bgfx_topology_convert(conversion, ptr::null_mut(), 0, src, num, I::INDEX32);
+
+        let mut output = vec![I::default(); max as usize];
+        let size = (output.len() * mem::size_of::<I>()) as u32;
+        let dst = output.as_mut_ptr() as *mut c_void;
+        let written = bgfx_sys::bgfx_topology_convert(conversion, dst, size, src, num, I::INDEX32);
+
+        output.truncate(written as usize);
+        output
+    }
+}
+
+/// Sorts the triangles of a triangle list, returning the sorted indices.
+///
+/// `dir` and `pos` are the view direction and position, in the same space as the vertices. The
+/// vertices are read from `vertices`, laid out according to `decl`, whose position must be
+/// declared as at least 3 floats. Indices past the last whole triangle are dropped.
+///
+/// Fails with `BgfxError::MissingAttrib` if `decl` lacks a float position, and with
+/// `BgfxError::OutOfBounds` if an index refers past the end of `vertices`.
+pub fn sort_tri_list<I: Index>(_bgfx: &Bgfx,
+                               sort: TopologySort,
+                               dir: [f32; 3],
+                               pos: [f32; 3],
+                               decl: &VertexDecl,
+                               vertices: &[u8],
+                               indices: &[I])
+                               -> Result<Vec<I>, BgfxError> {
+    let offset = match decl.attrib(Attrib::Position) {
+        Some(info) if info.kind == AttribType::Float && info.count >= 3 => info.offset as usize,
+        _ => return Err(BgfxError::MissingAttrib(Attrib::Position)),
+    };
+
+    let stride = decl.stride() as usize;
+    let position_end = offset + 3 * mem::size_of::<f32>();
+
+    let indices = &indices[..indices.len() - indices.len() % 3];
+    for index in indices {
+        let start = index.to_usize().checked_mul(stride);
+        let end = start.and_then(|start| start.checked_add(position_end));
+        if end.map_or(true, |end| end > vertices.len()) {
+            return Err(BgfxError::OutOfBounds);
+        }
+    }
+
+    let mut output = vec![I::default(); indices.len()];
+    if output.is_empty() {
+        return Ok(output);
+    }
+
+    unsafe {
+        // bgfx reads the position from the start of each vertex, so point it at the position of
+        // the first one.
+        bgfx_sys::bgfx_topology_sort_tri_list(sort as bgfx_sys::bgfx_topology_sort_t,
+                                              output.as_mut_ptr() as *mut c_void,
+                                              (output.len() * mem::size_of::<I>()) as u32,
+                                              dir.as_ptr(),
+                                              pos.as_ptr(),
+                                              vertices.as_ptr().add(offset) as *const c_void,
+                                              stride as u32,
+                                              indices.as_ptr() as *const c_void,
+                                              indices.len() as u32,
+                                              I::INDEX32);
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn strips_need_a_whole_primitive() {
+        assert_eq!(TopologyConvert::TriStripToTriList.min_indices(), 3);
+        assert_eq!(TopologyConvert::LineStripToLineList.min_indices(), 2);
+        assert_eq!(TopologyConvert::TriListToLineList.min_indices(), 1);
+    }
+
+}
@@ -0,0 +1,354 @@
+// Copyright (c) 2015-2016, Johan Sköld.
+// License: http://opensource.org/licenses/ISC
+
+//! Vertex data utilities.
+//!
+//! Inspecting [`VertexDecl`] layouts, and reading, writing and converting raw vertex data laid
+//! out according to them.
+//!
+//! [`VertexDecl`]: ../struct.VertexDecl.html
+
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::os::raw::c_void;
+
+use bgfx_sys;
+
+use {Attrib, AttribType, Bgfx, BgfxError, VertexDecl};
+
+/// Marks an attribute as absent from a declaration.
+const ATTRIB_ABSENT: u16 = 0xffff;
+
+/// All attributes, in the order bgfx numbers them.
+const ATTRIBS: [Attrib; 16] = [Attrib::Position,
+                               Attrib::Normal,
+                               Attrib::Tangent,
+                               Attrib::Bitangent,
+                               Attrib::Color0,
+                               Attrib::Color1,
+                               Attrib::Indices,
+                               Attrib::Weight,
+                               Attrib::TexCoord0,
+                               Attrib::TexCoord1,
+                               Attrib::TexCoord2,
+                               Attrib::TexCoord3,
+                               Attrib::TexCoord4,
+                               Attrib::TexCoord5,
+                               Attrib::TexCoord6,
+                               Attrib::TexCoord7];
+
+/// Layout of an attribute within a vertex, as returned by [`VertexDecl::attrib`].
+///
+/// [`VertexDecl::attrib`]: struct.VertexDecl.html#method.attrib
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct AttribInfo {
+    /// The attribute.
+    pub attrib: Attrib,
+
+    /// Offset from the start of the vertex, in bytes.
+    pub offset: u16,
+
+    /// Number of components.
+    pub count: u8,
+
+    /// Type of each component.
+    pub kind: AttribType,
+}
+
+/// Iterator over the attributes of a [`VertexDecl`]. See [`VertexDecl::attribs`].
+///
+/// [`VertexDecl`]: struct.VertexDecl.html
+/// [`VertexDecl::attribs`]: struct.VertexDecl.html#method.attribs
+pub struct Attribs<'d> {
+    decl: &'d VertexDecl,
+    next: usize,
+}
+
+impl<'d> Iterator for Attribs<'d> {
+    type Item = AttribInfo;
+
+    fn next(&mut self) -> Option<AttribInfo> {
+        while let Some(&attrib) = ATTRIBS.get(self.next) {
+            self.next += 1;
+            if let Some(info) = self.decl.attrib(attrib) {
+                return Some(info);
+            }
+        }
+        None
+    }
+}
+
+/// Vertex type with a known layout.
+///
+/// With the `derive` feature enabled, this can be derived with `#[derive(Vertex)]`, which builds
+/// the declaration from `#[attrib(...)]` annotations on the fields. See the `bgfx-derive` crate
+/// for details.
+pub trait Vertex {
+
+    /// Builds the declaration describing the layout of the type.
+    fn decl() -> VertexDecl;
+
+}
+
+impl VertexDecl {
+
+    /// Size of a vertex, in bytes.
+    #[inline]
+    pub fn stride(&self) -> u16 {
+        self.decl.stride
+    }
+
+    /// Whether the declaration contains `attrib`.
+    #[inline]
+    pub fn has(&self, attrib: Attrib) -> bool {
+        self.decl.attributes[attrib as usize] != ATTRIB_ABSENT
+    }
+
+    /// Gets the layout of `attrib`, or `None` if the declaration lacks it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let decl = bgfx::VertexDecl::new(None)
+    ///                .add(bgfx::Attrib::Position, 3, bgfx::AttribType::Float)
+    ///                .add(bgfx::Attrib::Normal, 4, bgfx::AttribType::Uint8(true))
+    ///                .end();
+    ///
+    /// let normal = decl.attrib(bgfx::Attrib::Normal).unwrap();
+    /// assert_eq!(normal.offset, 12);
+    /// assert_eq!(normal.count, 4);
+    /// assert_eq!(normal.kind, bgfx::AttribType::Uint8(true));
+    /// assert_eq!(decl.stride(), 16);
+    /// ```
+    pub fn attrib(&self, attrib: Attrib) -> Option<AttribInfo> {
+        let encoded = self.decl.attributes[attrib as usize];
+        if encoded == ATTRIB_ABSENT {
+            return None;
+        }
+
+        // Encoded by `bgfx_vertex_decl_add` as (count - 1) in bits 0-1, the type in bits 3-5, the
+        // normalized flag in bit 7 and the as-int flag in bit 8.
+        let count = (encoded & 3) as u8 + 1;
+        let normalized = encoded & (1 << 7) != 0;
+        let as_int = encoded & (1 << 8) != 0;
+
+        let kind = match ((encoded >> 3) & 7) as bgfx_sys::bgfx_attrib_type_t {
+            bgfx_sys::BGFX_ATTRIB_TYPE_UINT8 if as_int => AttribType::Int8(normalized),
+            bgfx_sys::BGFX_ATTRIB_TYPE_UINT8 => AttribType::Uint8(normalized),
+            bgfx_sys::BGFX_ATTRIB_TYPE_UINT10 if as_int => AttribType::Int10(normalized),
+            bgfx_sys::BGFX_ATTRIB_TYPE_UINT10 => AttribType::Uint10(normalized),
+            bgfx_sys::BGFX_ATTRIB_TYPE_INT16 if as_int => AttribType::Int16(normalized),
+            bgfx_sys::BGFX_ATTRIB_TYPE_INT16 => AttribType::Uint16(normalized),
+            bgfx_sys::BGFX_ATTRIB_TYPE_HALF => AttribType::Half,
+            _ => AttribType::Float,
+        };
+
+        Some(AttribInfo {
+            attrib: attrib,
+            offset: self.decl.offset[attrib as usize],
+            count: count,
+            kind: kind,
+        })
+    }
+
+    /// Iterates over the attributes in the declaration, in the order of the `Attrib` variants.
+    #[inline]
+    pub fn attribs(&self) -> Attribs<'_> {
+        Attribs { decl: self, next: 0 }
+    }
+
+    /// Writes `attrib` of vertex `index` in `data`, converting from floats to the declared type.
+    /// Only as many components as the attribute was declared with are written.
+    ///
+    /// If `normalized` is `true`, `input` is expected to be in the -1 to 1 range, and is scaled to
+    /// the 0 to 1 range before being packed into unsigned attributes.
+    ///
+    /// Fails with `BgfxError::MissingAttrib` if the declaration lacks `attrib`, and with
+    /// `BgfxError::OutOfBounds` if `data` does not hold vertex `index`.
+    pub fn pack(&self,
+                attrib: Attrib,
+                normalized: bool,
+                input: [f32; 4],
+                data: &mut [u8],
+                index: usize)
+                -> Result<(), BgfxError> {
+        self.check_attrib(attrib)?;
+        self.check_index(data, index)?;
+
+        unsafe {
+            bgfx_sys::bgfx_vertex_pack(input.as_ptr(),
+                                       normalized,
+                                       attrib as bgfx_sys::bgfx_attrib_t,
+                                       &self.decl,
+                                       data.as_mut_ptr() as *mut c_void,
+                                       index as u32);
+        }
+
+        Ok(())
+    }
+
+    /// Reads `attrib` of vertex `index` in `data`, converted to floats. Components the attribute
+    /// was not declared with are returned as 0.
+    ///
+    /// Fails with `BgfxError::MissingAttrib` if the declaration lacks `attrib`, and with
+    /// `BgfxError::OutOfBounds` if `data` does not hold vertex `index`.
+    pub fn unpack(&self, attrib: Attrib, data: &[u8], index: usize) -> Result<[f32; 4], BgfxError> {
+        self.check_attrib(attrib)?;
+        self.check_index(data, index)?;
+
+        let mut output = [0.0; 4];
+        unsafe {
+            bgfx_sys::bgfx_vertex_unpack(output.as_mut_ptr(),
+                                         attrib as bgfx_sys::bgfx_attrib_t,
+                                         &self.decl,
+                                         data.as_ptr() as *const c_void,
+                                         index as u32);
+        }
+
+        Ok(output)
+    }
+
+    /// Converts the vertices in `data` from this declaration to `dest`. Attributes missing from
+    /// this declaration are zeroed in the output, and attributes missing from `dest` are dropped.
+    ///
+    /// Fails with `BgfxError::OutOfBounds` if `data` ends in the middle of a vertex.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let src = bgfx::VertexDecl::new(None)
+    ///               .add(bgfx::Attrib::Position, 3, bgfx::AttribType::Float)
+    ///               .end();
+    /// let dest = bgfx::VertexDecl::new(None)
+    ///                .add(bgfx::Attrib::Position, 4, bgfx::AttribType::Half)
+    ///                .end();
+    ///
+    /// let mut vertices = vec![0u8; 12 * 3];
+    /// for i in 0..3 {
+    ///     src.pack(bgfx::Attrib::Position, false, [i as f32, 0.0, 1.0, 0.0], &mut vertices, i)
+    ///         .unwrap();
+    /// }
+    ///
+    /// let packed = src.convert(&vertices, &dest).unwrap();
+    /// assert_eq!(packed.len(), 8 * 3);
+    /// ```
+    pub fn convert(&self, data: &[u8], dest: &VertexDecl) -> Result<Vec<u8>, BgfxError> {
+        let num = self.num_vertices(data)?;
+        let mut output = vec![0; num * dest.decl.stride as usize];
+
+        if num > 0 && !output.is_empty() {
+            unsafe {
+                bgfx_sys::bgfx_vertex_convert(&dest.decl,
+                                              output.as_mut_ptr() as *mut c_void,
+                                              &self.decl,
+                                              data.as_ptr() as *const c_void,
+                                              num as u32);
+            }
+        }
+
+        Ok(output)
+    }
+
+    /// Finds vertices in `data` whose positions are within `epsilon` of each other.
+    ///
+    /// Returns a remap table with an entry per vertex, holding the index of the first vertex it
+    /// was welded to (or its own index, if it is the first of its group), along with the number of
+    /// unique vertices.
+    ///
+    /// Like the [`topology`] helpers, this processes a whole mesh, and requires bgfx to be
+    /// initialized. bgfx only welds up to 65535 vertices at once, so the remap table always holds
+    /// `u16` indices.
+    ///
+    /// Fails with `BgfxError::MissingAttrib` if the declaration lacks a position, and with
+    /// `BgfxError::OutOfBounds` if `data` ends in the middle of a vertex or holds more than 65535
+    /// vertices.
+    ///
+    /// [`topology`]: topology/index.html
+    pub fn weld(&self,
+                _bgfx: &Bgfx,
+                data: &[u8],
+                epsilon: f32)
+                -> Result<(Vec<u16>, usize), BgfxError> {
+        self.check_attrib(Attrib::Position)?;
+
+        let num = self.num_vertices(data)?;
+        if num > u16::MAX as usize {
+            return Err(BgfxError::OutOfBounds);
+        }
+
+        let mut remap = vec![0; num];
+        if num == 0 {
+            return Ok((remap, 0));
+        }
+
+        let unique = unsafe {
+            bgfx_sys::bgfx_weld_vertices(remap.as_mut_ptr(),
+                                         &self.decl,
+                                         data.as_ptr() as *const c_void,
+                                         num as u16,
+                                         epsilon)
+        };
+
+        Ok((remap, unique as usize))
+    }
+
+    /// Fails with `BgfxError::MissingAttrib` unless the declaration contains `attrib`.
+    fn check_attrib(&self, attrib: Attrib) -> Result<(), BgfxError> {
+        if self.has(attrib) { Ok(()) } else { Err(BgfxError::MissingAttrib(attrib)) }
+    }
+
+    /// Fails with `BgfxError::OutOfBounds` unless `data` holds all of vertex `index`.
+    fn check_index(&self, data: &[u8], index: usize) -> Result<(), BgfxError> {
+        let stride = self.decl.stride as usize;
+        let end = index.checked_add(1).and_then(|n| n.checked_mul(stride));
+
+        match end {
+            Some(end) if end <= data.len() && index <= u32::MAX as usize => Ok(()),
+            _ => Err(BgfxError::OutOfBounds),
+        }
+    }
+
+    /// Number of vertices in `data`. Fails with `BgfxError::OutOfBounds` if `data` ends in the
+    /// middle of a vertex.
+    fn num_vertices(&self, data: &[u8]) -> Result<usize, BgfxError> {
+        let stride = self.decl.stride as usize;
+
+        match stride {
+            0 => Ok(0),
+            _ if data.len() % stride != 0 => Err(BgfxError::OutOfBounds),
+            _ if data.len() / stride > u32::MAX as usize => Err(BgfxError::OutOfBounds),
+            _ => Ok(data.len() / stride),
+        }
+    }
+
+}
+
+/// Declarations are equal if they have the same layout.
+impl PartialEq for VertexDecl {
+    fn eq(&self, other: &VertexDecl) -> bool {
+        self.decl.hash == other.decl.hash && self.decl.stride == other.decl.stride &&
+        self.decl.offset == other.decl.offset &&
+        self.decl.attributes == other.decl.attributes
+    }
+}
+
+impl Eq for VertexDecl {}
+
+impl Hash for VertexDecl {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.decl.hash.hash(state);
+    }
+}
+
+/// Lists the stride and the attributes, ordered by offset.
+impl fmt::Debug for VertexDecl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut attribs: Vec<AttribInfo> = self.attribs().collect();
+        attribs.sort_by_key(|info| info.offset);
+
+        f.debug_struct("VertexDecl")
+            .field("stride", &self.decl.stride)
+            .field("attribs", &attribs)
+            .finish()
+    }
+}
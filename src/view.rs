@@ -0,0 +1,199 @@
+// Copyright (c) 2015-2016, Johan Sköld.
+// License: http://opensource.org/licenses/ISC
+
+//! View configuration.
+
+use {BackbufferRatio, Bgfx, ClearFlags};
+
+/// Rectangle a view is displayed in.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum ViewRect {
+    /// Fixed rectangle, given as x, y, width and height.
+    Fixed(u16, u16, u16, u16),
+
+    /// Rectangle at x, y, sized relative to the backbuffer.
+    Auto(u16, u16, BackbufferRatio),
+}
+
+/// How a view is cleared.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum ViewClear {
+    /// Clear with a single color, given as RGBA.
+    Color {
+        flags: ClearFlags,
+        rgba: u32,
+        depth: f32,
+        stencil: u8,
+    },
+
+    /// Clear each frame buffer attachment with a color from the palette.
+    Mrt {
+        flags: ClearFlags,
+        depth: f32,
+        stencil: u8,
+        colors: [u8; 8],
+    },
+}
+
+/// View settings builder.
+///
+/// Collects the settings of a single view, and applies them all at once through [`apply`]. Only
+/// the settings that have been set are applied; anything else is left as it is. Since the
+/// settings are kept around, printing a `View` with `{:?}` shows how a view was configured.
+///
+/// # Example
+///
+/// ```no_run
+/// # let bgfx: bgfx::Bgfx = unimplemented!();
+/// bgfx::View::new(0)
+///     .name("Scene")
+///     .rect_auto(0, 0, bgfx::BackbufferRatio::Equal)
+///     .clear(bgfx::CLEAR_COLOR | bgfx::CLEAR_DEPTH, 0x303030ff, 1.0, 0)
+///     .apply(&bgfx);
+/// ```
+///
+/// [`apply`]: #method.apply
+#[derive(Debug, Clone, PartialEq)]
+pub struct View {
+    id: u8,
+    name: Option<String>,
+    rect: Option<ViewRect>,
+    scissor: Option<(u16, u16, u16, u16)>,
+    clear: Option<ViewClear>,
+    seq: Option<bool>,
+    transform: Option<([f32; 16], [f32; 16])>,
+}
+
+impl View {
+
+    /// Creates an empty set of settings for view `id`.
+    #[inline]
+    pub fn new(id: u8) -> View {
+        View {
+            id: id,
+            name: None,
+            rect: None,
+            scissor: None,
+            clear: None,
+            seq: None,
+            transform: None,
+        }
+    }
+
+    /// Sets the name of the view.
+    #[inline]
+    pub fn name(&mut self, name: &str) -> &mut Self {
+        self.name = Some(name.to_owned());
+        self
+    }
+
+    /// Sets the rectangle to display the view in.
+    #[inline]
+    pub fn rect(&mut self, x: u16, y: u16, width: u16, height: u16) -> &mut Self {
+        self.rect = Some(ViewRect::Fixed(x, y, width, height));
+        self
+    }
+
+    /// Sets the rectangle to display the view in, sized relative to the backbuffer.
+    #[inline]
+    pub fn rect_auto(&mut self, x: u16, y: u16, ratio: BackbufferRatio) -> &mut Self {
+        self.rect = Some(ViewRect::Auto(x, y, ratio));
+        self
+    }
+
+    /// Sets the scissor rectangle of the view.
+    #[inline]
+    pub fn scissor(&mut self, x: u16, y: u16, width: u16, height: u16) -> &mut Self {
+        self.scissor = Some((x, y, width, height));
+        self
+    }
+
+    /// Clears the view with a single color.
+    #[inline]
+    pub fn clear(&mut self, flags: ClearFlags, rgba: u32, depth: f32, stencil: u8) -> &mut Self {
+        self.clear = Some(ViewClear::Color {
+            flags: flags,
+            rgba: rgba,
+            depth: depth,
+            stencil: stencil,
+        });
+        self
+    }
+
+    /// Clears each frame buffer attachment of the view with a color from the palette.
+    #[inline]
+    pub fn clear_mrt(&mut self,
+                     flags: ClearFlags,
+                     depth: f32,
+                     stencil: u8,
+                     colors: [u8; 8])
+                     -> &mut Self {
+        self.clear = Some(ViewClear::Mrt {
+            flags: flags,
+            depth: depth,
+            stencil: stencil,
+            colors: colors,
+        });
+        self
+    }
+
+    /// Enables or disables sequential mode for the view.
+    #[inline]
+    pub fn seq(&mut self, enabled: bool) -> &mut Self {
+        self.seq = Some(enabled);
+        self
+    }
+
+    /// Sets the view and projection matrices of the view.
+    #[inline]
+    pub fn transform(&mut self, view: &[f32; 16], proj: &[f32; 16]) -> &mut Self {
+        self.transform = Some((*view, *proj));
+        self
+    }
+
+    /// The id of the view these settings apply to.
+    #[inline]
+    pub fn id(&self) -> u8 {
+        self.id
+    }
+
+    /// Applies the settings to the view.
+    pub fn apply(&self, bgfx: &Bgfx) {
+        let id = self.id;
+
+        if let Some(ref name) = self.name {
+            bgfx.set_view_name(id, name);
+        }
+
+        match self.rect {
+            Some(ViewRect::Fixed(x, y, width, height)) => {
+                bgfx.set_view_rect(id, x, y, width, height)
+            }
+            Some(ViewRect::Auto(x, y, ratio)) => bgfx.set_view_rect_auto(id, x, y, ratio),
+            None => {}
+        }
+
+        if let Some((x, y, width, height)) = self.scissor {
+            bgfx.set_view_scissor(id, x, y, width, height);
+        }
+
+        match self.clear {
+            Some(ViewClear::Color { flags, rgba, depth, stencil }) => {
+                bgfx.set_view_clear(id, flags, rgba, depth, stencil)
+            }
+            Some(ViewClear::Mrt { flags, depth, stencil, colors }) => {
+                bgfx.set_view_clear_mrt(id, flags, depth, stencil, colors)
+            }
+            None => {}
+        }
+
+        if let Some(enabled) = self.seq {
+            bgfx.set_view_seq(id, enabled);
+        }
+
+        if let Some((ref view, ref proj)) = self.transform {
+            bgfx.set_view_transform(id, view, proj);
+        }
+    }
+
+}